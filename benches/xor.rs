@@ -0,0 +1,69 @@
+//! Times encoding with large blocks and high degrees, and the byte XOR
+//! kernel against a plain byte loop
+//!
+//! Run with and without the `parallel` feature to compare the XOR paths:
+//!
+//! ```bash
+//! cargo bench --bench xor
+//! cargo bench --bench xor --features parallel
+//! ```
+use luby_transform::{xor_bytes, Encoder};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn mib_per_sec(bytes: usize, elapsed: Duration) -> f64 {
+    bytes as f64 / (1 << 20) as f64 / elapsed.as_secs_f64()
+}
+
+fn main() {
+    let k: usize = 32;
+    let block_len: usize = 1 << 18; // 1 MiB per block
+    let source_blocks: Vec<Vec<i32>> = (0..k as i32)
+        .map(|b| (0..block_len as i32).map(|i| i ^ (b << 20)).collect())
+        .collect();
+    let mut encoder = Encoder::new_default(source_blocks, Some(1));
+    
+    let rounds = 64;
+    let mut symbols = 0;
+    let start = Instant::now();
+    for (_, d, _, block) in encoder.iter().take(rounds) {
+        symbols += d * block.len();
+    }
+    let elapsed = start.elapsed();
+    
+    let mode = if cfg!(feature = "parallel") { "parallel" } else { "serial" };
+    println!(
+        "{}: {} blocks in {:?} ({:.1} MiB XORed/s)",
+        mode,
+        rounds,
+        elapsed,
+        mib_per_sec(symbols * 4, elapsed)
+    );
+    
+    // Byte payloads: one byte per step against `u128` words
+    let len = 1 << 20;
+    let src: Vec<u8> = (0..len).map(|i| (i * 31) as u8).collect();
+    let mut dst = vec![0u8; len];
+    let passes = 256;
+    
+    let start = Instant::now();
+    for _ in 0..passes {
+        for (d, s) in black_box(&mut dst).iter_mut().zip(black_box(&src)) {
+            *d ^= s;
+        }
+    }
+    let bytewise = start.elapsed();
+    
+    let start = Instant::now();
+    for _ in 0..passes {
+        xor_bytes(black_box(&mut dst), black_box(&src));
+    }
+    let wordwise = start.elapsed();
+    
+    println!(
+        "bytes: {:.1} MiB/s byte by byte, {:.1} MiB/s as words ({:.1}x)",
+        mib_per_sec(passes * len, bytewise),
+        mib_per_sec(passes * len, wordwise),
+        bytewise.as_secs_f64() / wordwise.as_secs_f64()
+    );
+}
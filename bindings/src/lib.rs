@@ -0,0 +1,6 @@
+//! WASM module for `luby_transform`
+//!
+//! The bindings live in the main crate behind its `std` feature; this crate
+//! only links them into a `cdylib` for `wasm-pack`.
+
+pub use ::luby_transform::*;
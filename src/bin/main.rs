@@ -17,7 +17,13 @@ fn main(){
     let mut i = 0;
     // 生成编码块并添加到解码器
     while !decoder.is_complete() { // 生成略多于源块数量的编码块以提高解码概率
-        let (seed, d, indices, encoded_block) = encoder.generate_encoded_block(None);
+        let (seed, d, indices, encoded_block) = match encoder.generate_encoded_block(None) {
+            Ok(block) => block,
+            Err(err) => {
+                println!("Encode Process Failed: {}", err);
+                return;
+            }
+        };
         println!("Encoded Block #{}, seed: {}, d: {}, block_ids: {:?}", i, seed, d, indices);
         i += 1;
         
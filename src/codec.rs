@@ -0,0 +1,199 @@
+use crate::{pack_blocks, source_digest, unpack_blocks, ByteOrder, CodecId, ConfigError, DecodeError, Decoder, DegreeDistribution, EncodeError, EncodedBlock, Encoder, DEFAULT_SEED};
+
+/// Everything a receiver needs besides the blocks themselves to decode the
+/// output of `Codec::encode_bytes`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CodecHeader {
+    /// Number of source blocks
+    pub k: usize,
+    /// Size of each source block in bytes
+    pub block_size: usize,
+    /// Length of the original data in bytes
+    pub data_len: usize,
+    /// Degree distribution the encoder sampled from
+    pub distribution: DegreeDistribution,
+    /// SHA-256 of the packed source blocks, see `source_digest`; `decode`
+    /// checks the result against it when present
+    pub source_digest: Option<[u8; 32]>,
+    /// Fingerprint of the encoder's parameters, see `CodecId`; `decode`
+    /// rejects blocks if its decoder disagrees. `None` for empty data.
+    pub codec_id: Option<CodecId>,
+}
+
+impl CodecHeader {
+    /// Number of `i32` symbols in each encoded block
+    pub fn symbols_per_block(&self) -> usize {
+        self.block_size.div_ceil(4)
+    }
+}
+
+/// Single-call API that splits bytes into blocks, encodes them, and
+/// reassembles the bytes on the receiving side
+pub struct Codec;
+
+impl Codec {
+    /// Splits `data` into `block_size`-byte source blocks (zero-padding the
+    /// last one) and returns the header together with an endless stream of
+    /// encoded blocks
+    ///
+    /// Empty data yields `k == 0` and no blocks. Fails if `block_size` is 0.
+    /// Each block is yielded as the encoder's result, so an encode error is
+    /// passed on rather than ending the stream.
+    pub fn encode_bytes(data: &[u8], block_size: usize) -> Result<(CodecHeader, impl Iterator<Item = Result<EncodedBlock, EncodeError>>), ConfigError> {
+        if block_size == 0 {
+            return Err(ConfigError::ZeroBlockSize);
+        }
+        
+        let byte_blocks: Vec<Vec<u8>> = data
+            .chunks(block_size)
+            .map(|chunk| {
+                let mut block = chunk.to_vec();
+                block.resize(block_size, 0);
+                block
+            })
+            .collect();
+        let source_blocks = pack_blocks(&byte_blocks, ByteOrder::Little);
+        let digest = source_digest(&source_blocks);
+        let distribution = DegreeDistribution::default();
+        
+        let mut encoder = (!source_blocks.is_empty()).then(|| {
+            let mut encoder = Encoder::with_distribution(source_blocks, distribution)
+                .expect("the default distribution is valid for k > 0");
+            encoder.reset_seed(DEFAULT_SEED);
+            encoder
+        });
+        let header = CodecHeader {
+            k: byte_blocks.len(),
+            block_size,
+            data_len: data.len(),
+            distribution,
+            source_digest: Some(digest),
+            codec_id: encoder.as_ref().map(Encoder::codec_id),
+        };
+        let blocks = std::iter::from_fn(move || {
+            let block = encoder.as_mut()?.generate_encoded_block(None);
+            Some(block.map(|(seed, degree, indices, data)| EncodedBlock::new_with_indices(seed, degree, indices, data)))
+        });
+        
+        Ok((header, blocks))
+    }
+    
+    /// Decodes blocks produced by `encode_bytes` back into the original data
+    ///
+    /// Blocks are consumed only until the decode completes. Fails if they run
+    /// out first, if a block has the wrong payload length, if the header's
+    /// distribution parameters are invalid, or if the decoded blocks do not
+    /// match the header's source digest.
+    pub fn decode(header: &CodecHeader, blocks: impl IntoIterator<Item = EncodedBlock>) -> Result<Vec<u8>, DecodeError> {
+        if header.k == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let symbols = header.symbols_per_block();
+        let mut decoder = Decoder::with_distribution(header.k, symbols, header.distribution)
+            .map_err(DecodeError::InvalidDistribution)?;
+        if let Some(digest) = header.source_digest {
+            decoder.set_source_digest(digest);
+        }
+        for block in blocks {
+            if block.symbols().len() != symbols {
+                return Err(DecodeError::BlockLengthMismatch {
+                    seed: block.seed(),
+                    expected: symbols,
+                    found: block.symbols().len(),
+                });
+            }
+            let data = block.symbols().to_vec();
+            match header.codec_id {
+                Some(id) => {
+                    decoder.add_encoded_block_checked(block.seed(), block.degree(), data, id).map_err(DecodeError::Mismatch)?;
+                }
+                None => {
+                    decoder.add_encoded_block(block.seed(), block.degree(), data);
+                }
+            }
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        
+        let Some(decoded) = decoder.get_all_decoded_blocks() else {
+            return Err(DecodeError::Incomplete {
+                decoded: decoder.decoded_count(),
+                k: header.k,
+            });
+        };
+        if header.source_digest.is_some() {
+            decoder.verify().map_err(DecodeError::Integrity)?;
+        }
+        
+        let mut data: Vec<u8> = unpack_blocks(&decoded, ByteOrder::Little)
+            .into_iter()
+            .flat_map(|mut block| {
+                block.truncate(header.block_size);
+                block
+            })
+            .collect();
+        data.truncate(header.data_len);
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntegrityError;
+    
+    #[test]
+    fn test_codec_round_trip() {
+        // Pseudo-random data whose length is not a multiple of the block size
+        let mut state = 0x9e37_79b9u32;
+        let data: Vec<u8> = (0..5003)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        
+        for block_size in [64, 250, 4096] {
+            let (header, blocks) = Codec::encode_bytes(&data, block_size).unwrap();
+            assert_eq!(header.k, data.len().div_ceil(block_size));
+            assert_eq!(Codec::decode(&header, blocks.take(10 * header.k + 50).map(Result::unwrap)), Ok(data.clone()));
+        }
+        
+        let (header, mut blocks) = Codec::encode_bytes(&[], 16).unwrap();
+        assert!(blocks.next().is_none());
+        assert_eq!(Codec::decode(&header, []), Ok(Vec::new()));
+    }
+    
+    #[test]
+    fn test_codec_rejects_bad_input() {
+        let data = vec![7u8; 1000];
+        assert!(matches!(Codec::encode_bytes(&data, 0), Err(ConfigError::ZeroBlockSize)));
+        
+        let (header, blocks) = Codec::encode_bytes(&data, 100).unwrap();
+        assert!(matches!(
+            Codec::decode(&header, blocks.take(3).map(Result::unwrap)),
+            Err(DecodeError::Incomplete { k: 10, .. })
+        ));
+        
+        let short = EncodedBlock::new(5, 1, &[1, 2]);
+        assert_eq!(
+            Codec::decode(&header, [short]),
+            Err(DecodeError::BlockLengthMismatch { seed: 5, expected: 25, found: 2 })
+        );
+        
+        let mismatched = CodecHeader { codec_id: Some(CodecId(0)), ..header };
+        let (_, blocks) = Codec::encode_bytes(&data, 100).unwrap();
+        assert!(matches!(Codec::decode(&mismatched, blocks.map(Result::unwrap)), Err(DecodeError::Mismatch(_))));
+        
+        let tampered = CodecHeader { source_digest: Some([0; 32]), ..header };
+        let (_, blocks) = Codec::encode_bytes(&data, 100).unwrap();
+        assert!(matches!(
+            Codec::decode(&tampered, blocks.take(200).map(Result::unwrap)),
+            Err(DecodeError::Integrity(IntegrityError::DigestMismatch { .. }))
+        ));
+    }
+}
@@ -0,0 +1,235 @@
+use super::decoder::Decoder;
+use super::encoder::Encoder;
+use super::error::{ConfigError, DecodeError};
+use super::prng::DEFAULT_SEED;
+#[cfg(not(feature = "std"))]
+use super::compat::Vec;
+#[cfg(feature = "std")]
+use wasm_bindgen::prelude::*;
+
+/// Byte order used when packing bytes into `i32` symbols
+///
+/// Four bytes are packed into each symbol. Encoder and decoder sides must
+/// agree on the order; data packed in one order and unpacked in the other
+/// comes back byte-swapped within every symbol.
+#[cfg_attr(feature = "std", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Packs bytes into `i32` symbols, zero-padding the final symbol
+pub fn bytes_to_symbols(bytes: &[u8], order: ByteOrder) -> Vec<i32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            match order {
+                ByteOrder::Little => i32::from_le_bytes(word),
+                ByteOrder::Big => i32::from_be_bytes(word),
+            }
+        })
+        .collect()
+}
+
+/// Unpacks `i32` symbols into bytes, four per symbol
+pub fn symbols_to_bytes(symbols: &[i32], order: ByteOrder) -> Vec<u8> {
+    symbols
+        .iter()
+        .flat_map(|&symbol| match order {
+            ByteOrder::Little => symbol.to_le_bytes(),
+            ByteOrder::Big => symbol.to_be_bytes(),
+        })
+        .collect()
+}
+
+/// Packs each byte block into `i32` symbols with `bytes_to_symbols`
+pub fn pack_blocks(blocks: &[Vec<u8>], order: ByteOrder) -> Vec<Vec<i32>> {
+    blocks.iter().map(|block| bytes_to_symbols(block, order)).collect()
+}
+
+/// Unpacks each symbol block into bytes with `symbols_to_bytes`
+pub fn unpack_blocks(blocks: &[Vec<i32>], order: ByteOrder) -> Vec<Vec<u8>> {
+    blocks.iter().map(|block| symbols_to_bytes(block, order)).collect()
+}
+
+/// Splits `data` into source blocks of `block_size` little-endian `i32`
+/// symbols, the unit `Decoder` block sizes are given in
+///
+/// Each block holds `4 * block_size` bytes; the last one is zero-padded.
+/// Returns the blocks together with `data.len()`, which `join_blocks` needs
+/// to drop the padding again. Empty data gives no blocks. Fails if
+/// `block_size` is 0.
+pub fn split_into_blocks(data: &[u8], block_size: usize) -> Result<(Vec<Vec<i32>>, usize), ConfigError> {
+    if block_size == 0 {
+        return Err(ConfigError::ZeroBlockSize);
+    }
+    
+    let blocks = data
+        .chunks(4 * block_size)
+        .map(|chunk| {
+            let mut block = bytes_to_symbols(chunk, ByteOrder::Little);
+            block.resize(block_size, 0);
+            block
+        })
+        .collect();
+    Ok((blocks, data.len()))
+}
+
+/// Inverse of `split_into_blocks`: concatenates the blocks' bytes and cuts
+/// them to `original_len`
+pub fn join_blocks(blocks: &[Vec<i32>], original_len: usize) -> Vec<u8> {
+    let mut data: Vec<u8> = blocks
+        .iter()
+        .flat_map(|block| symbols_to_bytes(block, ByteOrder::Little))
+        .collect();
+    data.truncate(original_len);
+    data
+}
+
+/// Encodes `data` in one call, returning an endless stream of
+/// `(seed, degree, payload)` packets
+///
+/// The data is cut into `block_size`-byte source blocks, the last one
+/// zero-padded, and encoded with the default distribution over `u8`
+/// symbols. Decode with `decode_data` given the same `data.len()` and
+/// `block_size`. Empty data yields no packets. Fails if `block_size` is 0.
+pub fn encode_data(data: &[u8], block_size: usize) -> Result<impl Iterator<Item = (i64, usize, Vec<u8>)>, ConfigError> {
+    if block_size == 0 {
+        return Err(ConfigError::ZeroBlockSize);
+    }
+    let source_blocks: Vec<Vec<u8>> = data.chunks(block_size).map(<[u8]>::to_vec).collect();
+    let mut encoder = (!source_blocks.is_empty()).then(|| Encoder::new_padded_generic(source_blocks, Some(DEFAULT_SEED)));
+    Ok(core::iter::from_fn(move || {
+        let (seed, degree, _, payload) = encoder.as_mut()?.generate_encoded_block(None).ok()?;
+        Some((seed, degree, payload))
+    }))
+}
+
+/// Recovers the `data_len` bytes encoded by `encode_data` from its packets,
+/// in any order and with any of them lost
+///
+/// Packets are consumed only until the decode completes. Fails with
+/// `DecodeError::Incomplete` if they run out first, or with
+/// `DecodeError::ZeroBlockSize` if `block_size` is 0.
+pub fn decode_data(data_len: usize, block_size: usize, packets: impl IntoIterator<Item = (i64, usize, Vec<u8>)>) -> Result<Vec<u8>, DecodeError> {
+    if block_size == 0 {
+        return Err(DecodeError::ZeroBlockSize);
+    }
+    let k = data_len.div_ceil(block_size);
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+    
+    let mut decoder: Decoder<u8> = Decoder::new_with_last_block_size_generic(k, block_size, data_len - (k - 1) * block_size);
+    for (seed, degree, payload) in packets {
+        decoder.add_encoded_block(seed, degree, payload);
+        if decoder.is_complete() {
+            break;
+        }
+    }
+    Ok(decoder.finalize()?.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::luby_transform::decoder::Decoder;
+    use crate::luby_transform::encoder::Encoder;
+
+    #[test]
+    fn test_round_trip_both_orders() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for order in [ByteOrder::Little, ByteOrder::Big] {
+            assert_eq!(symbols_to_bytes(&bytes_to_symbols(&bytes, order), order), bytes);
+        }
+
+        // The final symbol is zero-padded
+        assert_eq!(bytes_to_symbols(&[1, 2, 3, 4, 5], ByteOrder::Little), vec![0x04030201, 5]);
+        assert_eq!(bytes_to_symbols(&[1, 2, 3, 4, 5], ByteOrder::Big), vec![0x01020304, 0x05000000]);
+    }
+
+    #[test]
+    fn test_mismatched_order_corrupts_bytes() {
+        let chunks: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i, i + 1, i + 2, i + 3]).collect();
+        let source_blocks = pack_blocks(&chunks, ByteOrder::Big);
+
+        let mut encoder = Encoder::new_default(source_blocks, Some(3));
+        let mut decoder = Decoder::new_default(chunks.len(), 1);
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+        }
+
+        let decoded = decoder.get_all_decoded_blocks().unwrap();
+        assert_eq!(unpack_blocks(&decoded, ByteOrder::Big), chunks);
+        for (symbols, chunk) in decoded.iter().zip(&chunks) {
+            assert_ne!(&symbols_to_bytes(symbols, ByteOrder::Little), chunk);
+        }
+    }
+    
+    #[test]
+    fn test_byte_blocks_round_trip() {
+        let chunks: Vec<Vec<u8>> = (0..12u8).map(|i| (0..64).map(|j| i.wrapping_mul(j)).collect()).collect();
+        let source_blocks = pack_blocks(&chunks, ByteOrder::Little);
+        assert!(source_blocks.iter().all(|block| block.len() == 16));
+        
+        let mut encoder = Encoder::new_default(source_blocks, Some(9));
+        let mut decoder = Decoder::new_default(chunks.len(), 16);
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+        }
+        
+        let decoded = decoder.get_all_decoded_blocks().unwrap();
+        assert_eq!(unpack_blocks(&decoded, ByteOrder::Little), chunks);
+    }
+    
+    #[test]
+    fn test_split_join_round_trip() {
+        let block_size = 5; // 20 bytes per block
+        for len in [0, 1, 19, 20, 21, 40, 99, 100] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 % 251) as u8).collect();
+            let (blocks, original_len) = split_into_blocks(&data, block_size).unwrap();
+            assert_eq!(original_len, len);
+            assert_eq!(blocks.len(), len.div_ceil(20));
+            assert!(blocks.iter().all(|block| block.len() == block_size));
+            assert_eq!(join_blocks(&blocks, original_len), data);
+        }
+        assert_eq!(split_into_blocks(&[1, 2, 3], 0), Err(ConfigError::ZeroBlockSize));
+        
+        // Through the codec as well
+        let data: Vec<u8> = (0..333).map(|i| i as u8).collect();
+        let (blocks, original_len) = split_into_blocks(&data, 8).unwrap();
+        let k = blocks.len();
+        let mut encoder = Encoder::new_default(blocks, Some(2));
+        let mut decoder = Decoder::new_default(k, 8);
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+        }
+        assert_eq!(join_blocks(&decoder.get_all_decoded_blocks().unwrap(), original_len), data);
+    }
+    
+    #[test]
+    fn test_encode_decode_data() {
+        let data: Vec<u8> = (0..5000u32).map(|i| (i % 253) as u8).collect();
+        
+        // Lose every third packet
+        let packets = encode_data(&data, 100).unwrap().enumerate().filter(|(i, _)| i % 3 != 0).map(|(_, packet)| packet);
+        assert_eq!(decode_data(data.len(), 100, packets), Ok(data.clone()));
+        
+        assert!(matches!(
+            decode_data(data.len(), 100, encode_data(&data, 100).unwrap().take(10)),
+            Err(DecodeError::Incomplete { k: 50, .. })
+        ));
+        assert_eq!(encode_data(&[], 100).unwrap().next(), None);
+        assert_eq!(decode_data(0, 100, Vec::new()), Ok(Vec::new()));
+        
+        assert!(matches!(encode_data(&data, 0), Err(ConfigError::ZeroBlockSize)));
+        assert_eq!(decode_data(data.len(), 0, Vec::new()), Err(DecodeError::ZeroBlockSize));
+    }
+}
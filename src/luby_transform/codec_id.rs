@@ -0,0 +1,93 @@
+use core::fmt;
+
+use super::crc::crc32;
+use super::prng::{DegreeDistribution, PRNG, SAMPLING_VERSION};
+#[cfg(not(feature = "std"))]
+use super::compat::vec;
+
+// Seed the generator is probed with, so custom LCG parameters change the id
+const PROBE_SEED: i64 = 1;
+
+/// Fingerprint of everything that decides which source blocks a seed
+/// selects: `k`, the degree distribution, the random generator and the
+/// `SAMPLING_VERSION` that draws from them
+///
+/// Encoder and decoder must agree on all of these or every block decodes to
+/// the wrong dependency set. Sending `Encoder::codec_id` alongside the blocks
+/// lets `Decoder::add_encoded_block_checked` reject the first block from a
+/// mismatched encoder instead of silently never completing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CodecId(pub u32);
+
+impl CodecId {
+    /// Computes the id of the parameters `prng` samples with
+    ///
+    /// The generator is identified by its name and its first outputs from a
+    /// fixed seed, which also tells apart LCGs with different constants.
+    pub fn of(prng: &PRNG) -> Self {
+        let mut bytes = vec![SAMPLING_VERSION];
+        bytes.extend_from_slice(&(prng.table().k() as u64).to_le_bytes());
+        match prng.distribution() {
+            DegreeDistribution::Ideal => bytes.push(0),
+            DegreeDistribution::Dense => bytes.push(2),
+            DegreeDistribution::Robust { delta, c } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&delta.to_bits().to_le_bytes());
+                bytes.extend_from_slice(&c.to_bits().to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(prng.source().name().as_bytes());
+
+        let mut probe = prng.source().clone_box();
+        probe.set_seed(PROBE_SEED);
+        for _ in 0..2 {
+            bytes.extend_from_slice(&probe.next_u32().to_le_bytes());
+        }
+
+        Self(crc32(&bytes))
+    }
+}
+
+impl fmt::Display for CodecId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::luby_transform::decoder::Decoder;
+    use crate::luby_transform::encoder::Encoder;
+    use crate::luby_transform::error::MismatchError;
+    use crate::luby_transform::prng::{LcgParams, DEFAULT_C, DEFAULT_DELTA};
+
+    #[test]
+    fn test_codec_id_mismatch() {
+        let source_blocks: Vec<Vec<i32>> = (0..100).map(|i| vec![i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(3));
+        let id = encoder.codec_id();
+
+        // Disagreeing on `k` fails on the very first block
+        let mut decoder = Decoder::new_default(99, 1);
+        let (seed, d, _, data) = encoder.generate_encoded_block(None).unwrap();
+        assert_eq!(
+            decoder.add_encoded_block_checked(seed, d, data.clone(), id),
+            Err(MismatchError { expected: decoder.codec_id(), found: id })
+        );
+        assert_eq!(decoder.blocks_received(), 0);
+
+        let mut decoder = Decoder::new_default(100, 1);
+        assert_eq!(decoder.codec_id(), id);
+        assert_eq!(decoder.add_encoded_block_checked(seed, d, data, id), Ok(Some(0)));
+
+        // Distribution and generator constants are covered as well
+        let robust = |delta, c| CodecId::of(&PRNG::new(100, delta, c).unwrap());
+        assert_eq!(robust(DEFAULT_DELTA, DEFAULT_C), id);
+        assert_ne!(robust(DEFAULT_DELTA, 0.2), id);
+        assert_ne!(robust(0.05, DEFAULT_C), id);
+        let lcg = LcgParams { a: 48271, m: LcgParams::MINSTD.m };
+        let custom = PRNG::with_lcg(100, DegreeDistribution::default(), lcg).unwrap();
+        assert_ne!(CodecId::of(&custom), id);
+    }
+}
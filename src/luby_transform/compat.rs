@@ -0,0 +1,60 @@
+//! `alloc` re-exports and float math that work with or without `std`
+//!
+//! The codec uses the `alloc` B-tree collections on every build, so public
+//! signatures do not change with the `std` feature. `ln` and `sqrt` always
+//! come from `libm`: the platform `ln` is not correctly rounded and varies
+//! between C libraries, and the degree tables must be bit-identical on every
+//! build that shares a code.
+
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::{boxed::Box, string::{String, ToString}, vec, vec::Vec};
+
+#[cfg(feature = "std")]
+pub(crate) fn floor(x: f64) -> f64 {
+    x.floor()
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn ceil(x: f64) -> f64 {
+    x.ceil()
+}
+
+pub(crate) use libm::{log as ln, sqrt};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use libm::{ceil, floor};
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Positive finite doubles spread over the whole exponent range
+    fn samples() -> impl Iterator<Item = f64> {
+        let mut state = 1u64;
+        core::iter::from_fn(move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            Some(f64::from_bits(state >> 2))
+        })
+        .filter(|x| *x > 0.0 && x.is_finite())
+        .take(60_000)
+    }
+    
+    #[test]
+    fn test_sqrt_matches_std() {
+        for x in samples().chain((1..10_000).map(|k| k as f64)) {
+            assert_eq!(sqrt(x).to_bits(), x.sqrt().to_bits(), "sqrt({x:e})");
+        }
+    }
+    
+    #[test]
+    fn test_ln_within_one_ulp_of_std() {
+        for x in samples().chain((1..10_000).map(|k| k as f64 / 0.05)) {
+            let ulps = (ln(x).to_bits() as i64 - x.ln().to_bits() as i64).abs();
+            assert!(ulps <= 1, "ln({x:e}) is {ulps} ulps from std");
+        }
+        assert_eq!(ln(1.0), 0.0);
+        assert_eq!(ln(0.0), f64::NEG_INFINITY);
+        assert!(ln(-1.0).is_nan());
+    }
+}
@@ -0,0 +1,216 @@
+use super::error::ConfigError;
+use super::prng::{DegreeDistribution, DistributionTable, DEFAULT_C, DEFAULT_DELTA};
+use alloc::sync::Arc;
+
+/// Code parameters shared by an encoder and its decoders
+///
+/// Built with `LtConfig::builder`, which validates everything up front, and
+/// passed to `Encoder::from_config` and `Decoder::from_config` so both sides
+/// are configured from one place.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LtConfig {
+    k: usize,
+    block_size: usize,
+    distribution: DegreeDistribution,
+    seed: Option<i64>,
+    systematic: bool,
+}
+
+/// Builder for `LtConfig`
+///
+/// `k` and `block_size` must be set; the distribution defaults to the Robust
+/// Soliton with `DEFAULT_DELTA` and `DEFAULT_C`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LtConfigBuilder {
+    k: usize,
+    block_size: usize,
+    distribution: DegreeDistribution,
+    seed: Option<i64>,
+    systematic: bool,
+}
+
+impl LtConfig {
+    /// Starts a builder with the default distribution and no seed
+    pub fn builder() -> LtConfigBuilder {
+        LtConfigBuilder::default()
+    }
+
+    /// Number of source blocks
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Length of each source block in symbols
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Degree distribution encoder and decoder sample from
+    pub fn distribution(&self) -> DegreeDistribution {
+        self.distribution
+    }
+
+    /// Seed the encoder starts from, if any
+    pub fn seed(&self) -> Option<i64> {
+        self.seed
+    }
+
+    /// Whether the encoder first sends the source blocks verbatim, see
+    /// `Encoder::new_systematic`
+    pub fn systematic(&self) -> bool {
+        self.systematic
+    }
+
+    /// Builds the degree table for these parameters, to share between
+    /// encoders and decoders via their `with_table` constructors
+    pub fn table(&self) -> Arc<DistributionTable> {
+        Arc::new(DistributionTable::build(self.k, self.distribution))
+    }
+}
+
+impl LtConfigBuilder {
+    /// Sets the number of source blocks
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Sets the length of each source block in symbols
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Sets the Robust Soliton failure probability
+    pub fn delta(mut self, delta: f64) -> Self {
+        let c = match self.distribution {
+            DegreeDistribution::Robust { c, .. } => c,
+            DegreeDistribution::Ideal | DegreeDistribution::Dense => DEFAULT_C,
+        };
+        self.distribution = DegreeDistribution::Robust { delta, c };
+        self
+    }
+
+    /// Sets the Robust Soliton tuning constant
+    pub fn c(mut self, c: f64) -> Self {
+        let delta = match self.distribution {
+            DegreeDistribution::Robust { delta, .. } => delta,
+            DegreeDistribution::Ideal | DegreeDistribution::Dense => DEFAULT_DELTA,
+        };
+        self.distribution = DegreeDistribution::Robust { delta, c };
+        self
+    }
+
+    /// Replaces the distribution, e.g. with `DegreeDistribution::Dense` for
+    /// short messages
+    pub fn distribution(mut self, distribution: DegreeDistribution) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
+    /// Sets the encoder's seed; without one, blocks need explicit seeds
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Makes the encoder send the `k` source blocks verbatim before any
+    /// repair blocks; decoders need no change
+    pub fn systematic(mut self, systematic: bool) -> Self {
+        self.systematic = systematic;
+        self
+    }
+
+    /// Validates the parameters and builds the config
+    ///
+    /// Fails if `block_size` is zero or if the distribution is invalid for
+    /// `k`, see `DegreeDistribution::validate`.
+    pub fn build(self) -> Result<LtConfig, ConfigError> {
+        if self.block_size == 0 {
+            return Err(ConfigError::ZeroBlockSize);
+        }
+        self.distribution.validate(self.k).map_err(ConfigError::Dist)?;
+        Ok(LtConfig {
+            k: self.k,
+            block_size: self.block_size,
+            distribution: self.distribution,
+            seed: self.seed,
+            systematic: self.systematic,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::luby_transform::decoder::Decoder;
+    use crate::luby_transform::encoder::Encoder;
+    use crate::luby_transform::error::{DistError, EncodeError};
+
+    #[test]
+    fn test_config_round_trip() {
+        let config = LtConfig::builder().k(20).block_size(3).delta(0.1).c(0.05).seed(9).build().unwrap();
+        assert_eq!(config.distribution(), DegreeDistribution::Robust { delta: 0.1, c: 0.05 });
+
+        let source_blocks: Vec<Vec<i32>> = (0..20).map(|i| vec![i, i * 2, i * 3]).collect();
+        let mut encoder = Encoder::from_config(source_blocks.clone(), &config).unwrap();
+        let mut decoder: Decoder = Decoder::from_config(&config);
+        assert_eq!(encoder.codec_id(), decoder.codec_id());
+        while !decoder.is_complete() {
+            let (seed, d, _, data) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, data);
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+
+        // Source blocks must match the config
+        assert_eq!(
+            Encoder::from_config(vec![vec![1, 2, 3]], &config).err(),
+            Some(EncodeError::SourceCountChanged { expected: 20, found: 1 })
+        );
+        let mut too_long: Vec<Vec<i32>> = vec![vec![0; 3]; 20];
+        too_long[4].push(1);
+        assert_eq!(
+            Encoder::from_config(too_long, &config).err(),
+            Some(EncodeError::MismatchedBlockLength { index: 4, expected: 3, found: 4 })
+        );
+
+        // A systematic config finishes on the first k blocks
+        let config = LtConfig::builder().k(20).block_size(3).seed(9).systematic(true).build().unwrap();
+        let mut encoder = Encoder::from_config(source_blocks.clone(), &config).unwrap();
+        let mut decoder: Decoder = Decoder::from_config(&config);
+        for (seed, d, _, data) in encoder.iter().take(20) {
+            decoder.add_encoded_block(seed, d, data);
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+
+        assert_eq!(LtConfig::builder().k(20).build(), Err(ConfigError::ZeroBlockSize));
+        assert_eq!(LtConfig::builder().block_size(3).build(), Err(ConfigError::Dist(DistError::NoSourceBlocks)));
+        assert!(LtConfig::builder().k(20).block_size(3).delta(2.0).build().is_err());
+    }
+
+    #[test]
+    fn test_dense_small_k() {
+        let k = 30;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, !i]).collect();
+        let blocks_needed = |distribution: DegreeDistribution, seed: i64| {
+            let config = LtConfig::builder().k(k).block_size(2).distribution(distribution).seed(seed).build().unwrap();
+            let mut encoder = Encoder::from_config(source_blocks.clone(), &config).unwrap();
+            let mut decoder: Decoder = Decoder::from_config(&config);
+            let mut received = 0;
+            while !decoder.is_complete() {
+                let (seed, d, _, data) = encoder.generate_encoded_block(None).unwrap();
+                decoder.add_encoded_block(seed, d, data);
+                received += 1;
+            }
+            assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+            received
+        };
+
+        // Averaged over many seeds, dense blocks finish a few blocks past k
+        let seeds = 1..=40;
+        let dense: usize = seeds.clone().map(|seed| blocks_needed(DegreeDistribution::Dense, seed)).sum();
+        let robust: usize = seeds.clone().map(|seed| blocks_needed(DegreeDistribution::default(), seed)).sum();
+        assert!(dense < robust);
+        assert!(dense < 40 * (k + 3), "dense = {}", dense);
+    }
+}
@@ -0,0 +1,58 @@
+/// Lookup table for the reflected IEEE CRC32 polynomial
+const TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Continues a CRC32 (IEEE) computation over `bytes`
+fn update(crc: u32, bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(crc, |crc, &b| TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+/// Computes the CRC32 (IEEE) of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !update(!0, bytes)
+}
+
+/// Computes the checksum of an encoded block over `seed || degree || data`
+///
+/// The seed is hashed as 8 little-endian bytes, the degree as 4, and each
+/// data symbol as 4, matching the block wire format.
+pub fn block_crc(seed: i64, degree: usize, data: &[i32]) -> u32 {
+    let mut crc = update(!0, &seed.to_le_bytes());
+    crc = update(crc, &(degree as u32).to_le_bytes());
+    for symbol in data {
+        crc = update(crc, &symbol.to_le_bytes());
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&42i64.to_le_bytes());
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&7i32.to_le_bytes());
+        assert_eq!(block_crc(42, 3, &[7]), crc32(&bytes));
+    }
+}
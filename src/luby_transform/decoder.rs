@@ -1,135 +1,931 @@
-use super::prng::PRNG;
-use std::collections::{HashMap, HashSet};
+use super::error::{BlockError, DecodeError, DistError, EncodeError, IntegrityError, LtError, MismatchError};
+use super::gaussian;
+use super::precode::Precode;
+use super::prng::{random_source_by_name, systematic_index, DegreeDistribution, DistributionTable, Minstd, RandomSource, PRNG};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use super::state::{BlockState, DecoderState};
+use super::xor::{xor_into, XorElement};
+use super::crc::block_crc;
+use super::sha256::Sha256;
+use super::codec_id::CodecId;
+use super::config::LtConfig;
+#[cfg(not(feature = "std"))]
+use super::compat::{vec, Box, ToString, Vec};
+
+/// Largest block count or block size `Decoder::restore` accepts
+pub const MAX_RESTORED_LEN: usize = 1 << 24;
+
+/// A received encoded block, reduced by every source block decoded so far
+///
+/// `dependencies` holds only the still-undecoded source indices and `data`
+/// has the decoded ones XORed out, so a block with a single dependency left
+/// is that source block. Precode constraints are stored the same way, with
+/// seed 0 and all-zero data.
+struct ReceivedBlock<T> {
+    seed: i64,
+    degree: usize, // Degree the block arrived with
+    dependencies: BTreeSet<usize>,
+    data: Vec<T>,
+    eliminated: bool, // Took part in a Gaussian elimination that decoded blocks
+    constraint: bool, // A precode parity relation rather than a received block
+}
+
+impl<T> ReceivedBlock<T> {
+    fn new(seed: i64, degree: usize, dependencies: BTreeSet<usize>, data: Vec<T>) -> Self {
+        Self { seed, degree, dependencies, data, eliminated: false, constraint: false }
+    }
+    
+    /// A precode parity relation, stored like a block with seed 0
+    fn constraint(dependencies: BTreeSet<usize>, data: Vec<T>) -> Self {
+        Self { constraint: true, ..Self::new(0, dependencies.len(), dependencies, data) }
+    }
+}
+
+/// Outcome of `Decoder::add_encoded_block_report`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddResult {
+    /// Index assigned to the block, or `None` if it was a duplicate or was
+    /// rejected
+    pub block_index: Option<usize>,
+    /// Source blocks decoded as a consequence of this block
+    pub newly_decoded: usize,
+    /// Whether every source block is now decoded
+    pub complete: bool,
+}
+
+/// What `Decoder` drops once `max_buffered_packets` blocks are held
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Drop the incoming block
+    #[default]
+    RejectNew,
+    /// Drop the held block with the most undecoded dependencies, the one
+    /// furthest from being useful
+    EvictHighestDegree,
+    /// Drop the block held the longest
+    EvictOldest,
+}
+
+/// Receives events from a `Decoder` as they happen, see
+/// `Decoder::set_observer`
+///
+/// Every method does nothing by default, so implementors only override the
+/// events they need.
+pub trait DecodeObserver<T>: Send {
+    /// Called once per source block as soon as it is decoded, with the final
+    /// block trimmed like in `get_all_decoded_blocks`
+    fn on_block_decoded(&mut self, _index: usize, _data: &[T]) {}
+
+    /// Called once, right after the last source block is decoded
+    fn on_complete(&mut self) {}
+
+    /// Called when decoding cannot continue even though at least as many
+    /// blocks are held as remain undecoded, e.g. to run `force_solve` or to
+    /// ask the sender for more blocks
+    fn on_stall(&mut self) {}
+}
 
 /// Decoder for Luby Transform codes
 /// 
 /// This decoder is responsible for reconstructing the original source blocks
 /// from a set of encoded blocks using the belief propagation algorithm.
+///
+/// Blocks hold `i32` symbols unless another `XorElement` is chosen; it must
+/// match the encoder's and is chosen with the `_generic` constructors.
+/// Snapshots, CRC checks and digest verification are available for `i32`
+/// blocks.
 #[allow(unused,unused_variables,dead_code, unused_imports)]
-pub struct Decoder {
+pub struct Decoder<T: XorElement = i32> {
     k: usize,            // Number of source blocks
-    block_size: usize,   // Size of each block in bytes
+    block_size: usize,   // Length of each block in symbols
+    last_block_size: Option<usize>, // Unpadded size of the final source block, if known
     prng: PRNG,          // PRNG for reconstructing block dependencies
-    received_blocks: HashMap<usize, (i64, usize, Vec<i32>)>, // Index -> (seed, degree, data)
-    decoded_blocks: HashMap<usize, Vec<i32>>,                // Decoded source blocks
+    codec_id: CodecId,   // `CodecId::of(&prng)`, computed once since `prng` is never replaced
+    precode: Option<Precode>,                              // Precode applied before LT encoding, if any
+    received_blocks: BTreeMap<usize, ReceivedBlock<T>>,       // Index -> received block, ordered so decoding is reproducible
+    held_packets: usize,                                   // Received blocks held, precode constraints excluded
+    dependents: BTreeMap<usize, BTreeSet<usize>>,          // Source index -> received blocks depending on it
+    pending: Vec<usize>,                                   // Received blocks to examine in the next round
+    decoded_blocks: BTreeMap<usize, Vec<T>>,                  // Decoded source blocks
     current_round: usize,                                  // Current decoding round
+    max_rounds: Option<usize>,                             // Peeling rounds allowed per `try_decode`, `None` for 2 * symbol count
+    budget_exhausted: bool,                                // Whether the last `try_decode` stopped at `max_rounds`
+    gaussian_fallback: bool,                               // Whether a stalled `try_decode` falls back to elimination
+    next_block_index: usize,                               // Index assigned to the next received block
+    blocks_wasted: usize,                                  // Blocks dropped because they carried no new information
+    blocks_received: usize,                                // Every block passed to `add_encoded_block`, duplicates included
+    blocks_corrupted: usize,                               // Blocks rejected by a CRC, degree or length check
+    blocks_evicted: usize,                                 // Blocks dropped to stay within `max_buffered`
+    max_buffered: Option<usize>,                           // Encoded blocks held at most, `None` for no limit
+    eviction_policy: EvictionPolicy,                       // What to drop once `max_buffered` is reached
+    observer: Option<Box<dyn DecodeObserver<T>>>,          // Notified of decode events, if set
+    source_digest: Option<[u8; 32]>,                       // Expected `source_digest` of the decoded blocks, if known
+    decode_log: Vec<(i64, usize)>,                         // (solving block seed, solved index) in solve order
+    seen_seeds: BTreeSet<i64>,                              // Seeds of every block accepted so far
+    newly_decoded: Vec<usize>,                             // Source indices decoded since the last `drain_newly_decoded`
 }
 
-impl Decoder {
+impl<T: XorElement> Decoder<T> {
     /// Creates a new Decoder with the specified parameters
-    pub fn new(k: usize, block_size: usize, delta: f64, c: f64) -> Self {
-        let prng = PRNG::new(k, delta, c);
-        
+    ///
+    /// Fails if the RSD parameters are invalid for `k`, see `validate_params`.
+    pub fn new_generic(k: usize, block_size: usize, delta: f64, c: f64) -> Result<Self, DistError> {
+        Self::with_distribution_generic(k, block_size, DegreeDistribution::Robust { delta, c })
+    }
+    
+    /// Creates a new Decoder sampling degrees from `distribution`
+    ///
+    /// `distribution` must match the one the encoder was built with.
+    pub fn with_distribution_generic(k: usize, block_size: usize, distribution: DegreeDistribution) -> Result<Self, DistError> {
+        Self::with_source_generic(k, block_size, distribution, Box::new(Minstd::default()))
+    }
+    
+    /// Creates a new Decoder drawing its random numbers from `source`
+    ///
+    /// `source` must be the same kind of generator the encoder used, or the
+    /// seeds will map to different source blocks. Only the built-in
+    /// generators from `random_source_by_name` survive `snapshot`/`restore`.
+    pub fn with_source_generic(k: usize, block_size: usize, distribution: DegreeDistribution, source: Box<dyn RandomSource>) -> Result<Self, DistError> {
+        distribution.validate(k)?;
+        Ok(Self::build(k, block_size, distribution, source, None))
+    }
+    
+    /// Creates a new Decoder with default parameters that trims the final
+    /// source block to `last_block_size` in `get_all_decoded_blocks`
+    ///
+    /// Use this when the data was split into `block_size` chunks and the last
+    /// chunk was zero-padded; `Encoder::last_block_size` reports the value.
+    pub fn new_with_last_block_size_generic(k: usize, block_size: usize, last_block_size: usize) -> Self {
+        let mut decoder = Self::new_default_generic(k, block_size);
+        decoder.last_block_size = Some(last_block_size);
+        decoder
+    }
+    
+    /// Sets the total length of the original data in symbols, as reported by
+    /// `Encoder::data_len`, so the padding of the final block is dropped
+    ///
+    /// Fails unless every block but the last would be full, i.e. unless
+    /// `data_len` lies in `((k - 1) * block_size, k * block_size]`, or is 0
+    /// for `k == 0`.
+    pub fn set_data_len(&mut self, data_len: usize) -> Result<(), DecodeError> {
+        let full = self.k.saturating_sub(1) * self.block_size;
+        let fits = match self.k {
+            0 => data_len == 0,
+            _ => data_len > full && data_len <= full + self.block_size,
+        };
+        if !fits {
+            return Err(DecodeError::DataLenOutOfRange { data_len, k: self.k, block_size: self.block_size });
+        }
+        self.last_block_size = (self.k > 0).then_some(data_len - full);
+        Ok(())
+    }
+    
+    /// Creates a new Decoder for blocks from `Encoder::with_precode`
+    ///
+    /// `redundancy` must match the value the encoder was built with. The
+    /// precode's parity relations are loaded as zero-valued equations, so they
+    /// take part in peeling and elimination like received blocks.
+    pub fn with_precode_generic(k: usize, block_size: usize, redundancy: f64) -> Result<Self, EncodeError> {
+        let precode = Precode::new(k, redundancy)?;
+        let mut decoder = Self::build(k, block_size, DegreeDistribution::default(), Box::new(Minstd::default()), Some(precode));
+        decoder.load_precode_constraints();
+        Ok(decoder)
+    }
+    
+    /// Inserts the precode parity relations as zero-valued equations
+    fn load_precode_constraints(&mut self) {
+        let Some(precode) = &self.precode else {
+            return;
+        };
+        for dependencies in precode.constraints() {
+            let block_index = self.next_block_index;
+            self.next_block_index += 1;
+            let data = vec![T::ZERO; self.block_size];
+            self.insert_block(block_index, ReceivedBlock::constraint(dependencies, data));
+        }
+    }
+    
+    /// Clears all received and decoded blocks so the decoder can be reused
+    /// for a new payload with the same parameters
+    ///
+    /// The precomputed degree distribution is kept rather than rebuilt.
+    pub fn reset(&mut self) {
+        self.received_blocks.clear();
+        self.held_packets = 0;
+        self.dependents.clear();
+        self.pending.clear();
+        self.decoded_blocks.clear();
+        self.current_round = 0;
+        self.budget_exhausted = false;
+        self.next_block_index = 0;
+        self.blocks_wasted = 0;
+        self.blocks_received = 0;
+        self.blocks_corrupted = 0;
+        self.blocks_evicted = 0;
+        self.source_digest = None;
+        self.decode_log.clear();
+        self.seen_seeds.clear();
+        self.newly_decoded.clear();
+        self.load_precode_constraints();
+    }
+    
+    /// Creates a new Decoder sampling degrees from a shared table, built for
+    /// the same `k` and distribution as the encoder's
+    pub fn with_table_generic(table: Arc<DistributionTable>, block_size: usize) -> Self {
+        Self::from_prng(table.k(), block_size, PRNG::with_table(table), None)
+    }
+    
+    fn build(k: usize, block_size: usize, distribution: DegreeDistribution, source: Box<dyn RandomSource>, precode: Option<Precode>) -> Self {
+        let parity_count = precode.as_ref().map_or(0, Precode::parity_count);
+        let prng = PRNG::build(k + parity_count, distribution, source);
+        Self::from_prng(k, block_size, prng, precode)
+    }
+    
+    fn from_prng(k: usize, block_size: usize, prng: PRNG, precode: Option<Precode>) -> Self {
         Self {
             k,
             block_size,
+            last_block_size: None,
+            codec_id: CodecId::of(&prng),
             prng,
-            received_blocks: HashMap::new(),
-            decoded_blocks: HashMap::new(),
+            precode,
+            received_blocks: BTreeMap::new(),
+            held_packets: 0,
+            dependents: BTreeMap::new(),
+            pending: Vec::new(),
+            decoded_blocks: BTreeMap::new(),
             current_round: 0,
+            max_rounds: None,
+            budget_exhausted: false,
+            gaussian_fallback: true,
+            next_block_index: 0,
+            blocks_wasted: 0,
+            blocks_received: 0,
+            blocks_corrupted: 0,
+            blocks_evicted: 0,
+            max_buffered: None,
+            eviction_policy: EvictionPolicy::default(),
+            observer: None,
+            source_digest: None,
+            decode_log: Vec::new(),
+            seen_seeds: BTreeSet::new(),
+            newly_decoded: Vec::new(),
         }
     }
     
+    /// Creates a new Decoder from a validated `LtConfig`
+    ///
+    /// Pair it with an encoder from `Encoder::from_config` with the same
+    /// config.
+    pub fn from_config_generic(config: &LtConfig) -> Self {
+        Self::with_table_generic(config.table(), config.block_size())
+    }
+    
     /// Creates a new Decoder with default parameters
-    pub fn new_default(k: usize, block_size: usize) -> Self {
-        let prng = PRNG::new_default(k);
+    pub fn new_default_generic(k: usize, block_size: usize) -> Self {
+        Self::build(k, block_size, DegreeDistribution::default(), Box::new(Minstd::default()), None)
+    }
+    
+    /// Adds an encoded block to the decoder
+    ///
+    /// Systematic blocks (reserved negative seeds, see `systematic_seed`) are
+    /// placed straight into the decoded set without peeling.
+    ///
+    /// Returns the index assigned to the block, or `None` if a block with the
+    /// same seed was already added. Equal seeds always yield equal dependency
+    /// sets, so a retransmitted block carries nothing new.
+    ///
+    /// `degree` must lie in `1..=k` (counting precode parity blocks) and
+    /// match the degree the decoder derives from `seed`
+    /// (1 for systematic blocks). A mismatch means the block was corrupted or
+    /// the encoder uses different `k` or distribution parameters; the block is
+    /// counted in `blocks_corrupted` and dropped, and `None` is returned.
+    ///
+    /// Likewise `data` must hold exactly `block_size` symbols. The one
+    /// exception is the systematic copy of the final source block, which may
+    /// be `last_block_size` symbols long and is zero-padded here.
+    pub fn add_encoded_block(&mut self, seed: i64, degree: usize, data: Vec<T>) -> Option<usize> {
+        self.add_encoded_block_with_progress(seed, degree, data, |_, _| {})
+    }
+    
+    /// Adds an encoded block stamped with the sender's `Encoder::codec_id`
+    ///
+    /// A block from an encoder with a different `k`, distribution or
+    /// generator is rejected with a `MismatchError` before it is looked at,
+    /// so the first such block already reports the misconfiguration.
+    /// Otherwise behaves like `add_encoded_block`.
+    pub fn add_encoded_block_checked(&mut self, seed: i64, degree: usize, data: Vec<T>, codec_id: CodecId) -> Result<Option<usize>, MismatchError> {
+        if codec_id != self.codec_id {
+            return Err(MismatchError { expected: self.codec_id, found: codec_id });
+        }
+        Ok(self.add_encoded_block(seed, degree, data))
+    }
+    
+    /// Returns the fingerprint of the decoder's `k`, distribution and
+    /// generator, see `CodecId`
+    pub fn codec_id(&self) -> CodecId {
+        self.codec_id
+    }
+    
+    /// Adds an encoded block like `add_encoded_block` and reports whether it
+    /// advanced the decode
+    ///
+    /// Senders can stop as soon as `complete` is true, without polling
+    /// `is_complete`.
+    pub fn add_encoded_block_report(&mut self, seed: i64, degree: usize, data: Vec<T>) -> AddResult {
+        let before = self.decoded_count();
+        let block_index = self.add_encoded_block(seed, degree, data);
+        AddResult {
+            block_index,
+            newly_decoded: self.decoded_count() - before,
+            complete: self.is_complete(),
+        }
+    }
+    
+    /// Adds an encoded block like `add_encoded_block`, reporting progress
+    ///
+    /// `progress` receives `(decoded_count, k)` after every peeling round
+    /// and after each step that decodes blocks outside peeling (systematic
+    /// blocks, Gaussian elimination), so the call that completes the decode
+    /// always reports `decoded_count == k`.
+    pub fn add_encoded_block_with_progress<F>(&mut self, seed: i64, degree: usize, data: Vec<T>, mut progress: F) -> Option<usize>
+    where
+        F: FnMut(usize, usize),
+    {
+        let (block_index, queued) = self.ingest(seed, degree, data, None, &mut progress).ok()??;
+        if queued {
+            self.try_decode(&mut progress);
+        }
+        Some(block_index)
+    }
+    
+    /// Adds an encoded block whose neighbours are given explicitly instead
+    /// of derived from its seed
+    ///
+    /// This decodes blocks from other LT implementations or custom degree
+    /// schedules. `indices` are the encoded blocks the data XORs together,
+    /// so duplicates in it cancel out and are ignored. The seed only
+    /// identifies the block: a repeated seed is dropped as a duplicate, and
+    /// negative seeds are not treated as systematic. Malformed blocks, with
+    /// no indices, an index out of range or the wrong length, are counted in
+    /// `blocks_corrupted` and `None` is returned.
+    pub fn add_encoded_block_with_indices(&mut self, seed: i64, indices: &[usize], data: Vec<T>) -> Option<usize> {
+        let mut neighbours = BTreeSet::new();
+        for &index in indices {
+            if !neighbours.insert(index) {
+                neighbours.remove(&index);
+            }
+        }
+        let (block_index, queued) = self.ingest(seed, neighbours.len(), data, Some(neighbours), &mut |_, _| {}).ok()??;
+        if queued {
+            self.try_decode(&mut |_, _| {});
+        }
+        Some(block_index)
+    }
+    
+    /// Adds an encoded block like `add_encoded_block`, but reports why a
+    /// malformed block was rejected
+    ///
+    /// Returns `Ok(None)` for duplicates. Rejected blocks are still counted
+    /// in `blocks_corrupted`.
+    pub fn try_add_encoded_block(&mut self, seed: i64, degree: usize, data: Vec<T>) -> Result<Option<usize>, BlockError> {
+        let Some((block_index, queued)) = self.ingest(seed, degree, data, None, &mut |_, _| {})? else {
+            return Ok(None);
+        };
+        if queued {
+            self.try_decode(&mut |_, _| {});
+        }
+        Ok(Some(block_index))
+    }
+    
+    /// Adds many encoded blocks, then peels once over all of them
+    ///
+    /// Each block is checked and stored exactly as by `add_encoded_block`,
+    /// but `try_decode` runs a single time at the end instead of after every
+    /// block, which is much faster for bulk or offline decoding. The decoded
+    /// blocks are the same as when adding the blocks one by one. Returns what
+    /// `add_encoded_block` would have returned for each block.
+    pub fn add_blocks_batch(&mut self, blocks: Vec<(i64, usize, Vec<T>)>) -> Vec<Option<usize>> {
+        let mut queued = false;
+        let indices = blocks
+            .into_iter()
+            .map(|(seed, degree, data)| {
+                let (block_index, block_queued) = self.ingest(seed, degree, data, None, &mut |_, _| {}).ok()??;
+                queued |= block_queued;
+                Some(block_index)
+            })
+            .collect();
+        if queued {
+            self.try_decode(&mut |_, _| {});
+        }
+        indices
+    }
+    
+    /// Checks a block and stores it, or decodes it directly if systematic,
+    /// without peeling
+    ///
+    /// `indices` replaces the dependencies the seed implies; such blocks are
+    /// never treated as systematic. Returns the assigned index and whether
+    /// anything was queued for `try_decode`, `None` for duplicates, or why
+    /// the block was rejected.
+    fn ingest(&mut self, seed: i64, degree: usize, mut data: Vec<T>, indices: Option<BTreeSet<usize>>, progress: &mut dyn FnMut(usize, usize)) -> Result<Option<(usize, bool)>, BlockError> {
+        // With no source blocks there is nothing to decode, or to sample from
+        if self.symbol_count() == 0 {
+            self.blocks_received += 1;
+            self.seen_seeds.insert(seed);
+            return Ok(None);
+        }
         
-        Self {
-            k,
-            block_size,
-            prng,
-            received_blocks: HashMap::new(),
-            decoded_blocks: HashMap::new(),
-            current_round: 0,
+        let systematic = if indices.is_some() { None } else { systematic_index(seed) };
+        let checked = match indices {
+            Some(indices) => self.check_indices(seed, indices, data.len()),
+            None => self.check_block(seed, degree, data.len()),
+        };
+        let dependencies = match checked {
+            Ok(dependencies) => dependencies,
+            Err(err) => {
+                lt_warn!("dropping {}", err);
+                self.blocks_corrupted += 1;
+                return Err(err);
+            }
+        };
+        data.resize(self.block_size, T::ZERO);
+        
+        self.blocks_received += 1;
+        if !self.seen_seeds.insert(seed) {
+            return Ok(None);
+        }
+        
+        // Assign a unique index; indices are never reused after removals
+        let block_index = self.next_block_index;
+        self.next_block_index += 1;
+        
+        if let Some(index) = systematic {
+            if index >= self.k || self.decoded_blocks.contains_key(&index) {
+                self.blocks_wasted += 1;
+                return Ok(Some((block_index, false)));
+            }
+            self.decode_log.push((seed, index));
+            self.mark_decoded(index, data);
+            progress(self.decoded_count(), self.k);
+            return Ok(Some((block_index, true)));
+        }
+        
+        // A block whose dependencies are all decoded carries no new information
+        let undecoded = dependencies.iter().filter(|idx| !self.decoded_blocks.contains_key(idx)).count();
+        if undecoded == 0 {
+            self.blocks_wasted += 1;
+            return Ok(Some((block_index, false)));
+        }
+        
+        // Blocks left with one dependency are peeled right away, so only
+        // those still waiting on several count against the limit
+        let buffered = undecoded > 1;
+        if buffered
+            && self.max_buffered.is_some_and(|limit| self.held_packets >= limit)
+            && self.eviction_policy == EvictionPolicy::RejectNew
+        {
+            self.blocks_evicted += 1;
+            return Ok(Some((block_index, false)));
+        }
+        
+        // Store the received block
+        self.insert_block(block_index, ReceivedBlock::new(seed, degree, dependencies, data));
+        if buffered {
+            self.enforce_buffer_limit();
         }
+        Ok(Some((block_index, true)))
     }
     
-    /// Adds an encoded block to the decoder
-    pub fn add_encoded_block(&mut self, seed: i64, degree: usize, data: Vec<i32>) -> usize {
-        // Store the received block with a unique index
-        let block_index = self.received_blocks.len();
-        self.received_blocks.insert(block_index, (seed, degree, data));
+    /// Evicts held blocks under the eviction policy until at most
+    /// `max_buffered` remain
+    ///
+    /// Precode constraints are never evicted and do not count towards the
+    /// limit.
+    fn enforce_buffer_limit(&mut self) {
+        let Some(limit) = self.max_buffered else {
+            return;
+        };
+        while self.held_packets > limit {
+            let held = self.received_blocks.iter().filter(|(_, block)| !block.constraint);
+            // Ties go to the oldest block, the first in index order
+            let victim = match self.eviction_policy {
+                EvictionPolicy::EvictHighestDegree => held
+                    .rev()
+                    .max_by_key(|(_, block)| block.dependencies.len())
+                    .map(|(&block_idx, _)| block_idx),
+                EvictionPolicy::EvictOldest | EvictionPolicy::RejectNew => held.map(|(&block_idx, _)| block_idx).next(),
+            };
+            let Some(block_idx) = victim else {
+                return;
+            };
+            self.remove_block(block_idx);
+            self.blocks_evicted += 1;
+        }
+    }
+    
+    /// Checks a block's length and declared degree, returning the
+    /// dependencies its seed implies (none for systematic blocks)
+    fn check_block(&self, seed: i64, degree: usize, len: usize) -> Result<BTreeSet<usize>, BlockError> {
+        // Mixing blocks from encoders with different block sizes would
+        // silently corrupt the XORs
+        let short_final = systematic_index(seed) == Some(self.k - 1) && self.last_block_size == Some(len);
+        if len != self.block_size && !short_final {
+            return Err(BlockError::LengthMismatch { seed, expected: self.block_size, found: len });
+        }
         
-        // Try to decode after each new block
-        self.try_decode();
+        // No encoder produces degree 0 or more than one block per index
+        if degree == 0 || degree > self.symbol_count() {
+            return Err(BlockError::DegreeOutOfRange { seed, degree, max: self.symbol_count() });
+        }
         
-        block_index
+        // Recompute the dependency set and check it against the declared degree
+        let systematic = systematic_index(seed);
+        let dependencies = match systematic {
+            Some(_) => BTreeSet::new(),
+            None if !self.prng.source().is_valid_seed(seed) => return Err(BlockError::InvalidSeed { seed }),
+            None => self.prng.src_blocks_for_seed(seed).1,
+        };
+        let expected = if systematic.is_some() { 1 } else { dependencies.len() };
+        if degree != expected {
+            return Err(BlockError::DegreeMismatch { seed, declared: degree, expected });
+        }
+        Ok(dependencies)
+    }
+    
+    /// Checks the length and explicit neighbours of a block from
+    /// `add_encoded_block_with_indices`
+    fn check_indices(&self, seed: i64, indices: BTreeSet<usize>, len: usize) -> Result<BTreeSet<usize>, BlockError> {
+        if len != self.block_size {
+            return Err(BlockError::LengthMismatch { seed, expected: self.block_size, found: len });
+        }
+        if indices.is_empty() {
+            return Err(BlockError::DegreeOutOfRange { seed, degree: 0, max: self.symbol_count() });
+        }
+        if let Some(&index) = indices.iter().find(|&&index| index >= self.symbol_count()) {
+            return Err(BlockError::IndexOutOfRange { seed, index, max: self.symbol_count() });
+        }
+        Ok(indices)
+    }
+    
+    /// Reduces a block by the decoded source blocks, then stores it and
+    /// indexes it under each remaining dependency
+    ///
+    /// The block is queued for peeling only if at most one dependency
+    /// remains; otherwise `mark_decoded` queues it once enough of them are
+    /// decoded.
+    fn insert_block(&mut self, block_index: usize, mut block: ReceivedBlock<T>) {
+        block.dependencies.retain(|idx| match self.decoded_blocks.get(idx) {
+            Some(decoded) => {
+                xor_into(&mut block.data, decoded);
+                false
+            }
+            None => true,
+        });
+        for &dep in &block.dependencies {
+            self.dependents.entry(dep).or_default().insert(block_index);
+        }
+        if block.dependencies.len() <= 1 {
+            self.pending.push(block_index);
+        }
+        if !block.constraint {
+            self.held_packets += 1;
+        }
+        self.received_blocks.insert(block_index, block);
+    }
+    
+    /// Removes a block and its entries in the dependency index
+    fn remove_block(&mut self, block_index: usize) -> Option<ReceivedBlock<T>> {
+        let block = self.received_blocks.remove(&block_index)?;
+        if !block.constraint {
+            self.held_packets -= 1;
+        }
+        for dep in &block.dependencies {
+            if let Some(blocks) = self.dependents.get_mut(dep) {
+                blocks.remove(&block_index);
+                if blocks.is_empty() {
+                    self.dependents.remove(dep);
+                }
+            }
+        }
+        Some(block)
+    }
+    
+    /// Drops a held block that can no longer decode anything, counting it as
+    /// wasted unless it is a precode constraint or took part in a successful
+    /// Gaussian elimination
+    fn release_block(&mut self, block_idx: usize) {
+        if let Some(block) = self.received_blocks.get(&block_idx)
+            && !block.constraint
+            && !block.eliminated
+        {
+            self.blocks_wasted += 1;
+        }
+        self.remove_block(block_idx);
+    }
+    
+    /// Drops every held block once the decode is complete
+    fn release_if_complete(&mut self) {
+        if !self.is_complete() {
+            return;
+        }
+        self.blocks_wasted += self
+            .received_blocks
+            .values()
+            .filter(|block| !block.constraint && !block.eliminated)
+            .count();
+        self.received_blocks = BTreeMap::new();
+        self.held_packets = 0;
+        self.dependents = BTreeMap::new();
+        self.pending = Vec::new();
+    }
+    
+    /// Stores a newly decoded block, XORs it out of every block depending on
+    /// it and queues those left with at most one undecoded dependency
+    ///
+    /// This is the ripple of the peeling decoder: a decoded block only
+    /// touches its own dependents. Source blocks are also queued for
+    /// `drain_newly_decoded` and reported to the observer.
+    fn mark_decoded(&mut self, index: usize, data: Vec<T>) {
+        if self.decoded_blocks.contains_key(&index) {
+            return;
+        }
+        if index < self.k {
+            self.newly_decoded.push(index);
+            let complete = self.decoded_count() + 1 == self.k;
+            if let Some(observer) = self.observer.as_mut() {
+                let len = match self.last_block_size {
+                    Some(last_len) if index + 1 == self.k => last_len.min(data.len()),
+                    _ => data.len(),
+                };
+                observer.on_block_decoded(index, &data[..len]);
+                if complete {
+                    observer.on_complete();
+                }
+            }
+        }
+        for block_idx in self.dependents.remove(&index).unwrap_or_default() {
+            if let Some(block) = self.received_blocks.get_mut(&block_idx) {
+                xor_into(&mut block.data, &data);
+                block.dependencies.remove(&index);
+                if block.dependencies.len() <= 1 {
+                    self.pending.push(block_idx);
+                }
+            }
+        }
+        self.decoded_blocks.insert(index, data);
     }
     
     /// Attempts to decode the source blocks using belief propagation
+    ///
+    /// Only blocks queued in `pending` are examined: blocks left with at most
+    /// one undecoded dependency, either on arrival or after a source block
+    /// decoded in the previous round. Each round therefore touches the
+    /// neighbourhood of fresh progress rather than every held block.
+    /// `progress` is called after each round.
+    ///
+    /// Blocks whose dependencies are all decoded are dropped as they are
+    /// examined, and all held blocks are dropped once the decode completes.
+    ///
+    /// At most `max_rounds` rounds run per call. If blocks are still queued
+    /// after that, the call stops early and sets `round_budget_exhausted`;
+    /// the queue is kept for `resume`.
     #[allow(unused,unused_variables,dead_code, unused_imports)]
-    fn try_decode(&mut self) {
-        let mut progress = true;
+    fn try_decode(&mut self, progress: &mut dyn FnMut(usize, usize)) {
+        self.budget_exhausted = false;
+        let mut rounds = 0;
         
         // Continue decoding rounds until no more progress is made
-        while progress {
-            progress = false;
+        while !self.pending.is_empty() {
+            if rounds == self.max_rounds() {
+                lt_warn!("stopping after {} decoding rounds with {} blocks queued", rounds, self.pending.len());
+                self.budget_exhausted = true;
+                return;
+            }
+            rounds += 1;
             self.current_round += 1;
             
-            let mut blocks_to_remove = Vec::new();
-            
-            // Process each received block
-            for (&block_idx, &(seed, degree, ref data)) in &self.received_blocks {
-                // Get the dependencies for this block
-                let (_, _, dependencies) = self.prng.get_src_blocks(Some(seed));
+            for block_idx in core::mem::take(&mut self.pending) {
+                let Some(block) = self.received_blocks.get(&block_idx) else {
+                    continue;
+                };
                 
-                // Filter out dependencies that are already decoded
-                let undecoded_deps: HashSet<_> = dependencies
-                    .iter()
-                    .filter(|&&idx| !self.decoded_blocks.contains_key(&idx))
-                    .cloned()
-                    .collect();
-                
-                // If only one undecoded dependency remains, we can decode it
-                if undecoded_deps.len() == 1 {
-                    let target_idx = *undecoded_deps.iter().next().unwrap();
-                    
-                    // Create a copy of the data to work with
-                    let mut decoded_data = data.clone();
-                    
-                    // XOR with all already decoded dependencies
-                    for &dep_idx in &dependencies {
-                        if dep_idx != target_idx && self.decoded_blocks.contains_key(&dep_idx) {
-                            let dep_data = &self.decoded_blocks[&dep_idx];
-                            for i in 0..decoded_data.len() {
-                                if i < dep_data.len() {
-                                    decoded_data[i] ^= dep_data[i];
-                                }
-                            }
-                        }
+                // A block with one dependency left is that source block
+                let target_idx = match block.dependencies.len() {
+                    1 => *block.dependencies.iter().next().unwrap(),
+                    // Nothing left to solve: the block can contribute nothing further
+                    0 => {
+                        self.release_block(block_idx);
+                        continue;
                     }
-                    
-                    // Store the newly decoded block
-                    self.decoded_blocks.insert(target_idx, decoded_data);
-                    blocks_to_remove.push(block_idx);
-                    progress = true;
-                }
+                    _ => continue,
+                };
+                
+                let block = self.remove_block(block_idx).unwrap();
+                self.decode_log.push((block.seed, target_idx));
+                self.mark_decoded(target_idx, block.data);
             }
-            
-            // Remove processed blocks
-            for block_idx in blocks_to_remove {
-                self.received_blocks.remove(&block_idx);
+            progress(self.decoded_count(), self.k);
+        }
+        
+        // Peeling stalled; fall back to elimination once enough blocks are held
+        let enough_held = |decoder: &Self| {
+            decoder.received_blocks.len() >= decoder.symbol_count() - decoder.decoded_blocks.len()
+        };
+        if self.gaussian_fallback
+            && !self.is_complete()
+            && enough_held(self)
+            && self.solve_gaussian() > 0
+        {
+            progress(self.decoded_count(), self.k);
+        }
+        if !self.is_complete()
+            && enough_held(self)
+            && let Some(observer) = self.observer.as_mut()
+        {
+            observer.on_stall();
+        }
+        
+        self.release_if_complete();
+    }
+    
+    /// Solves the remaining source blocks by Gaussian elimination over GF(2)
+    ///
+    /// Every received block becomes one equation over the still-undecoded
+    /// source indices. Blocks whose dependencies end up fully decoded are
+    /// released afterwards. Returns the number of newly decoded blocks.
+    fn solve_gaussian(&mut self) -> usize {
+        let unknowns: Vec<usize> = (0..self.symbol_count())
+            .filter(|idx| !self.decoded_blocks.contains_key(idx))
+            .collect();
+        
+        // Equations follow block index order, so the solve is reproducible
+        let block_indices: Vec<usize> = self.received_blocks.keys().cloned().collect();
+        let equations = block_indices
+            .iter()
+            .map(|block_idx| {
+                let block = &self.received_blocks[block_idx];
+                (block.dependencies.clone(), block.data.clone())
+            })
+            .collect();
+        
+        let solution = gaussian::solve(&unknowns, equations);
+        let newly_decoded = solution.solved.len();
+        for (index, data) in solution.solved {
+            self.mark_decoded(index, data);
+        }
+        if newly_decoded > 0 {
+            for block_idx in &block_indices {
+                self.received_blocks.get_mut(block_idx).unwrap().eliminated = true;
             }
         }
+        
+        // Elimination already used everything the held blocks determine
+        self.pending.clear();
+        for block_idx in block_indices {
+            if self.received_blocks[&block_idx].dependencies.is_empty() {
+                self.release_block(block_idx);
+            }
+        }
+        
+        newly_decoded
+    }
+    
+    /// Runs Gaussian elimination over the held blocks regardless of how many
+    /// there are, recovering whatever the received blocks determine
+    ///
+    /// Returns true if the decode is complete afterwards.
+    pub fn force_solve(&mut self) -> bool {
+        if !self.is_complete() {
+            self.solve_gaussian();
+        }
+        self.is_complete()
+    }
+    
+    /// Attempts to recover the last missing source block in a single pass
+    ///
+    /// When exactly one source block is missing, any received block that
+    /// depends on it can finish the decode regardless of its degree. This
+    /// avoids the repeated full sweeps of `try_decode` in the common
+    /// "stuck on the last block" case. Returns true if the decode is complete.
+    pub fn try_finish_last(&mut self) -> bool {
+        let symbol_count = self.symbol_count();
+        if self.decoded_blocks.len() + 1 != symbol_count {
+            return self.is_complete();
+        }
+        
+        let Some(missing) = (0..symbol_count).find(|idx| !self.decoded_blocks.contains_key(idx)) else {
+            return self.is_complete();
+        };
+        
+        // Held blocks are already reduced, so any block still depending on
+        // the missing one holds its data
+        let finisher = self.received_blocks.iter().find(|(_, block)| block.dependencies.contains(&missing)).map(|(&block_idx, _)| block_idx);
+        if let Some(block) = finisher.and_then(|block_idx| self.remove_block(block_idx)) {
+            self.decode_log.push((block.seed, missing));
+            self.mark_decoded(missing, block.data);
+            self.pending.clear();
+        }
+        
+        self.release_if_complete();
+        self.is_complete()
+    }
+    
+    /// Number of intermediate blocks the LT layer encodes: the source blocks
+    /// plus any precode parity blocks
+    fn symbol_count(&self) -> usize {
+        self.k + self.precode.as_ref().map_or(0, Precode::parity_count)
+    }
+    
+    /// Returns the number of source blocks
+    pub fn k(&self) -> usize {
+        self.k
+    }
+    
+    /// Returns the length of each block in symbols
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+    
+    /// Returns the number of received blocks held for later peeling because
+    /// they still combine several undecoded source blocks
+    ///
+    /// Precode constraints are not counted. Together with `decoded_count`
+    /// and `blocks_received` this gives "decoded / missing / buffered"
+    /// progress.
+    pub fn pending_count(&self) -> usize {
+        self.held_packets
     }
     
     /// Returns the number of successfully decoded source blocks
     pub fn decoded_count(&self) -> usize {
-        self.decoded_blocks.len()
+        if self.precode.is_none() {
+            self.decoded_blocks.len()
+        } else {
+            self.decoded_blocks.keys().filter(|&&idx| idx < self.k).count()
+        }
     }
     
     /// Returns true if all source blocks have been decoded
     pub fn is_complete(&self) -> bool {
-        self.decoded_blocks.len() == self.k
+        self.decoded_count() == self.k
+    }
+    
+    /// Returns the number of held blocks with exactly one undecoded
+    /// dependency, i.e. blocks peeling can use right away
+    pub fn reducible_blocks(&self) -> usize {
+        self.received_blocks.values().filter(|block| block.dependencies.len() == 1).count()
+    }
+    
+    /// Returns true if the decode is incomplete and no held block can be
+    /// peeled, so progress needs more blocks or `force_solve`
+    pub fn is_stalled(&self) -> bool {
+        !self.is_complete() && self.reducible_blocks() == 0
+    }
+    
+    /// Returns the sorted source indices that have not been decoded yet
+    pub fn missing_indices(&self) -> Vec<usize> {
+        (0..self.k).filter(|idx| !self.decoded_blocks.contains_key(idx)).collect()
+    }
+    
+    /// Returns the sorted source indices that have been decoded
+    pub fn decoded_indices(&self) -> Vec<usize> {
+        (0..self.k).filter(|idx| self.decoded_blocks.contains_key(idx)).collect()
+    }
+    
+    /// Returns the current peeling graph: each held block index, in
+    /// ascending order, with its sorted still-undecoded dependencies
+    ///
+    /// Precode constraints appear as blocks too, and their dependencies may
+    /// include parity indices at or above `k`. Together with
+    /// `decoded_indices` this is the full bipartite graph between received
+    /// blocks and source blocks. Decoded sources drop out of the lists as
+    /// peeling progresses, and blocks disappear once they are used up.
+    pub fn graph_snapshot(&self) -> Vec<(usize, Vec<usize>)> {
+        self.received_blocks
+            .iter()
+            .map(|(&block_idx, block)| {
+                let mut undecoded: Vec<usize> = block.dependencies.iter().copied().collect();
+                undecoded.sort_unstable();
+                (block_idx, undecoded)
+            })
+            .collect()
+    }
+    
+    /// Sets the SHA-256 digest of the source blocks, see `source_digest`,
+    /// for `verify` to check the decoded blocks against
+    pub fn set_source_digest(&mut self, digest: [u8; 32]) {
+        self.source_digest = Some(digest);
     }
     
     /// Gets a decoded source block by index
-    pub fn get_decoded_block(&self, index: usize) -> Option<&Vec<i32>> {
+    pub fn get_decoded_block(&self, index: usize) -> Option<&Vec<T>> {
         self.decoded_blocks.get(&index)
     }
     
     /// Gets all decoded source blocks in order
-    pub fn get_all_decoded_blocks(&self) -> Option<Vec<Vec<i32>>> {
+    pub fn get_all_decoded_blocks(&self) -> Option<Vec<Vec<T>>> {
         if !self.is_complete() {
             return None;
         }
@@ -139,44 +935,1685 @@ impl Decoder {
             result.push(self.decoded_blocks[&i].clone());
         }
         
+        // Strip the zero padding of the final block
+        if let (Some(last_len), Some(last)) = (self.last_block_size, result.last_mut()) {
+            last.truncate(last_len);
+        }
+        
         Some(result)
     }
     
+    /// Returns a bitmap of the decoded source blocks to send back to the
+    /// encoder's `apply_feedback`
+    ///
+    /// Bit `i % 8` of byte `i / 8` is set once source block `i` is decoded.
+    pub fn feedback(&self) -> Vec<u8> {
+        let mut bitmap = vec![0u8; self.k.div_ceil(8)];
+        for &index in self.decoded_blocks.keys().filter(|&&idx| idx < self.k) {
+            bitmap[index / 8] |= 1 << (index % 8);
+        }
+        bitmap
+    }
+    
+    /// Returns every source block decoded so far, `None` for the missing
+    /// ones, in source order
+    ///
+    /// Unlike `get_all_decoded_blocks` this works on an incomplete decode,
+    /// e.g. to salvage what arrived before a transfer was aborted. The final
+    /// block is trimmed like in `get_all_decoded_blocks`.
+    pub fn get_partial_blocks(&self) -> Vec<Option<Vec<T>>> {
+        (0..self.k)
+            .map(|i| {
+                let mut block = self.decoded_blocks.get(&i)?.clone();
+                if i + 1 == self.k
+                    && let Some(last_len) = self.last_block_size
+                {
+                    block.truncate(last_len);
+                }
+                Some(block)
+            })
+            .collect()
+    }
+    
+    /// Returns the source blocks decoded since the last call, as (index, data)
+    /// in the order they were decoded
+    ///
+    /// Every source block is returned exactly once per payload, so consumers
+    /// can use blocks as they arrive instead of waiting for completion. The
+    /// final block is trimmed like in `get_all_decoded_blocks`. Blocks loaded
+    /// by `restore` are not returned.
+    pub fn drain_newly_decoded(&mut self) -> Vec<(usize, Vec<T>)> {
+        self.newly_decoded
+            .drain(..)
+            .map(|index| {
+                let mut data = self.decoded_blocks[&index].clone();
+                if index + 1 == self.k
+                    && let Some(last_len) = self.last_block_size
+                {
+                    data.truncate(last_len);
+                }
+                (index, data)
+            })
+            .collect()
+    }
+    
+    /// Decoded source blocks in order, the final one trimmed, without copying
+    fn ordered_blocks(&self) -> Option<impl Iterator<Item = &[T]>> {
+        if !self.is_complete() {
+            return None;
+        }
+        Some((0..self.k).map(|i| {
+            let block = self.decoded_blocks[&i].as_slice();
+            match self.last_block_size {
+                Some(last_len) if i + 1 == self.k => &block[..last_len.min(block.len())],
+                _ => block,
+            }
+        }))
+    }
+    
+    /// Returns the original data, all source blocks concatenated in order
+    /// with the final one trimmed, once every block is decoded
+    ///
+    /// With `set_data_len` this is exactly the data the encoder was built
+    /// from.
+    pub fn reassemble(&self) -> Option<Vec<T>> {
+        Some(self.ordered_blocks()?.flatten().copied().collect())
+    }
+    
+    /// Iterates over the decoded source blocks in order without copying them,
+    /// the final one trimmed like in `get_all_decoded_blocks`
+    ///
+    /// Returns `None` until every source block is decoded.
+    pub fn iter_decoded(&self) -> Option<impl Iterator<Item = &[T]>> {
+        self.ordered_blocks()
+    }
+    
+    /// Consumes the decoder and returns the source blocks in order without
+    /// copying them, or `None` if some are still missing
+    ///
+    /// Like `finalize`, but for callers that do not need the error details.
+    pub fn into_decoded_blocks(self) -> Option<Vec<Vec<T>>> {
+        self.finalize().ok()
+    }
+    
+    /// Consumes the decoder and returns the source blocks in order, trimmed
+    /// like `get_all_decoded_blocks`
+    ///
+    /// Unlike `get_all_decoded_blocks` this moves the blocks out instead of
+    /// copying them. Fails if some source blocks have not been decoded.
+    pub fn finalize(mut self) -> Result<Vec<Vec<T>>, DecodeError> {
+        if !self.is_complete() {
+            return Err(DecodeError::Incomplete { decoded: self.decoded_count(), k: self.k });
+        }
+        self.received_blocks = BTreeMap::new();
+        self.dependents = BTreeMap::new();
+        
+        let mut result: Vec<Vec<T>> = (0..self.k)
+            .map(|i| self.decoded_blocks.remove(&i).unwrap())
+            .collect();
+        if let (Some(last_len), Some(last)) = (self.last_block_size, result.last_mut()) {
+            last.truncate(last_len);
+        }
+        
+        Ok(result)
+    }
+    
     /// Gets the current decoding round
     pub fn current_round(&self) -> usize {
         self.current_round
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
     
-    #[test]
-    fn test_decoder_initialization() {
-        let decoder = Decoder::new_default(10, 1024);
-        assert_eq!(decoder.k, 10);
-        assert_eq!(decoder.block_size, 1024);
-        assert_eq!(decoder.decoded_count(), 0);
+    /// Returns how many peeling rounds a single block addition may run,
+    /// by default twice the number of intermediate blocks
+    ///
+    /// Each round that continues decodes at least one block, so the default
+    /// is never reached on valid input; the cap only bounds the work done
+    /// on malformed blocks.
+    pub fn max_rounds(&self) -> usize {
+        self.max_rounds.unwrap_or(2 * self.symbol_count()).max(1)
     }
     
-    #[test]
-    #[allow(unused,unused_variables,dead_code, unused_imports)]
-    fn test_add_block() {
-        let mut decoder = Decoder::new_default(2, 3);
-        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
-        
-        // With just one block of degree 1, we should be able to decode one source block
-        assert!(decoder.decoded_count() as i64 >= 0);
+    /// Sets the per-call round budget, see `max_rounds`
+    pub fn set_max_rounds(&mut self, rounds: usize) {
+        self.max_rounds = Some(rounds);
     }
     
-    #[test]
-    fn test_decode_complete() {
-        let mut decoder = Decoder::new_default(2, 3);
-        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
-        decoder.add_encoded_block(43, 1, vec![4, 5, 6]);
-        
-        // With two blocks of degree 1, we should be able to decode all source blocks
-        assert!(decoder.is_complete());
+    /// Returns the most encoded blocks held at once, if limited
+    pub fn max_buffered_packets(&self) -> Option<usize> {
+        self.max_buffered
+    }
+    
+    /// Limits the encoded blocks held while waiting to be peeled, so a flood
+    /// of blocks that never become decodable cannot grow memory without
+    /// bound
+    ///
+    /// Once `limit` blocks are held, `policy` decides whether a new block is
+    /// rejected or an older one evicted; either way the dropped block is
+    /// counted in `blocks_evicted`. Its seed stays seen, so a resent copy is
+    /// ignored as a duplicate. Precode constraints do not count. If more
+    /// than `limit` blocks are already held, the oldest extra ones are
+    /// evicted (the highest degree ones under `EvictHighestDegree`).
+    pub fn set_max_buffered_packets(&mut self, limit: usize, policy: EvictionPolicy) {
+        self.max_buffered = Some(limit);
+        self.eviction_policy = policy;
+        self.enforce_buffer_limit();
+    }
+    
+    /// Limits held encoded blocks to those whose data fits in `bytes`, see
+    /// `set_max_buffered_packets`
+    pub fn set_max_memory_bytes(&mut self, bytes: usize, policy: EvictionPolicy) {
+        let block_bytes = (self.block_size * core::mem::size_of::<T>()).max(1);
+        self.set_max_buffered_packets(bytes / block_bytes, policy);
+    }
+    
+    /// Removes the buffered packet limit
+    pub fn clear_max_buffered_packets(&mut self) {
+        self.max_buffered = None;
+    }
+    
+    /// Checks whether the last peeling pass stopped at `max_rounds` with
+    /// blocks still queued
+    pub fn round_budget_exhausted(&self) -> bool {
+        self.budget_exhausted
+    }
+    
+    /// Checks whether a stalled peeling pass falls back to Gaussian
+    /// elimination, enabled by default
+    ///
+    /// The fallback runs once the held blocks are at least as many as the
+    /// undecoded ones, which lowers the reception overhead for small `k`.
+    pub fn gaussian_fallback(&self) -> bool {
+        self.gaussian_fallback
+    }
+    
+    /// Enables or disables the Gaussian fallback, see `gaussian_fallback`
+    ///
+    /// With it disabled, decoding is pure peeling and `force_solve` runs
+    /// the elimination on demand.
+    pub fn set_gaussian_fallback(&mut self, enabled: bool) {
+        self.gaussian_fallback = enabled;
+    }
+    
+    /// Sets an observer notified of decoded blocks, completion and stalls,
+    /// replacing any previous one
+    ///
+    /// Blocks decoded before the observer is set, or loaded by `restore`,
+    /// are not reported.
+    pub fn set_observer(&mut self, observer: impl DecodeObserver<T> + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+    
+    /// Removes the observer and returns it
+    pub fn take_observer(&mut self) -> Option<Box<dyn DecodeObserver<T>>> {
+        self.observer.take()
+    }
+    
+    /// Continues peeling blocks left queued by an exhausted round budget
+    ///
+    /// Returns true if the decode is complete afterwards.
+    pub fn resume(&mut self) -> bool {
+        if !self.pending.is_empty() {
+            self.try_decode(&mut |_, _| {});
+        }
+        self.is_complete()
+    }
+    
+    /// Returns the number of received blocks dropped without helping the
+    /// decode: their dependencies were all decoded by other blocks, or they
+    /// were still held when the decode completed
+    pub fn blocks_wasted(&self) -> usize {
+        self.blocks_wasted
+    }
+    
+    /// Returns the number of blocks passed to `add_encoded_block`, including
+    /// duplicates
+    pub fn blocks_received(&self) -> usize {
+        self.blocks_received
+    }
+    
+    /// Returns the number of blocks dropped because a block with the same
+    /// seed had already been received
+    ///
+    /// Equal seeds give equal blocks, so retransmissions are recognised from
+    /// the seed alone and never stored twice.
+    pub fn duplicates_received(&self) -> usize {
+        self.blocks_received - self.seen_seeds.len()
+    }
+    
+    /// Returns the number of blocks rejected because their CRC, declared
+    /// degree or length did not match
+    pub fn blocks_corrupted(&self) -> usize {
+        self.blocks_corrupted
+    }
+    
+    /// Returns the number of blocks rejected or evicted to stay within
+    /// `max_buffered_packets`
+    pub fn blocks_evicted(&self) -> usize {
+        self.blocks_evicted
+    }
+    
+    /// Returns `blocks_received / k`, the decoding overhead
+    ///
+    /// The ratio is meaningful once `is_complete` returns true; a value of
+    /// 1.0 would mean every received block was needed. A decoder with
+    /// `k == 0` needs no blocks and reports 0.0.
+    pub fn overhead_ratio(&self) -> f64 {
+        if self.k == 0 {
+            return 0.0;
+        }
+        self.blocks_received as f64 / self.k as f64
+    }
+    
+    /// Returns the number of received blocks that did not help the decode
+    ///
+    /// This counts duplicates, blocks dropped as wasted, and held blocks whose
+    /// dependencies have all been decoded without them. Blocks that took part
+    /// in a successful Gaussian elimination count as contributing.
+    pub fn redundant_blocks(&self) -> usize {
+        let duplicates = self.duplicates_received();
+        let unused = self
+            .received_blocks
+            .values()
+            .filter(|block| !block.constraint && !block.eliminated)
+            .filter(|block| block.dependencies.is_empty())
+            .count();
+        duplicates + self.blocks_wasted + self.blocks_evicted + unused
+    }
+    
+    /// Returns every belief-propagation substitution in solve order
+    ///
+    /// Each entry is `(solving_block_seed, solved_index)`. Blocks recovered by
+    /// Gaussian elimination are not listed since no single block solves them;
+    /// precode constraints appear with seed 0. The log is not part of
+    /// `snapshot` and starts empty after `restore`.
+    pub fn decode_log(&self) -> &[(i64, usize)] {
+        &self.decode_log
+    }
+}
+
+#[cfg(feature = "std")]
+impl Decoder<u8> {
+    /// Writes the decoded data to `writer`, blocks in order with the final
+    /// one trimmed like in `reassemble`, and returns the bytes written
+    ///
+    /// Fails with an `InvalidData` error wrapping `DecodeError::Incomplete`
+    /// if some source blocks are still missing.
+    pub fn reassemble_into_writer<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        let blocks = self.ordered_blocks().ok_or_else(|| self.incomplete_io_error())?;
+        let mut written = 0;
+        for block in blocks {
+            writer.write_all(block)?;
+            written += block.len();
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: XorElement> Decoder<T> {
+    fn incomplete_io_error(&self) -> std::io::Error {
+        let err = DecodeError::Incomplete { decoded: self.decoded_count(), k: self.k };
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+impl Decoder {
+    /// Creates a new Decoder with the specified parameters
+    ///
+    /// Fails if the RSD parameters are invalid for `k`, see `validate_params`.
+    /// `new_generic` builds a decoder for any `XorElement` symbol type.
+    pub fn new(k: usize, block_size: usize, delta: f64, c: f64) -> Result<Self, DistError> {
+        Self::new_generic(k, block_size, delta, c)
+    }
+    
+    /// `with_distribution_generic` for `i32` symbols
+    pub fn with_distribution(k: usize, block_size: usize, distribution: DegreeDistribution) -> Result<Self, DistError> {
+        Self::with_distribution_generic(k, block_size, distribution)
+    }
+    
+    /// `with_source_generic` for `i32` symbols
+    pub fn with_source(k: usize, block_size: usize, distribution: DegreeDistribution, source: Box<dyn RandomSource>) -> Result<Self, DistError> {
+        Self::with_source_generic(k, block_size, distribution, source)
+    }
+    
+    /// `new_with_last_block_size_generic` for `i32` symbols
+    pub fn new_with_last_block_size(k: usize, block_size: usize, last_block_size: usize) -> Self {
+        Self::new_with_last_block_size_generic(k, block_size, last_block_size)
+    }
+    
+    /// `with_precode_generic` for `i32` symbols
+    pub fn with_precode(k: usize, block_size: usize, redundancy: f64) -> Result<Self, EncodeError> {
+        Self::with_precode_generic(k, block_size, redundancy)
+    }
+    
+    /// `with_table_generic` for `i32` symbols
+    pub fn with_table(table: Arc<DistributionTable>, block_size: usize) -> Self {
+        Self::with_table_generic(table, block_size)
+    }
+    
+    /// `from_config_generic` for `i32` symbols
+    pub fn from_config(config: &LtConfig) -> Self {
+        Self::from_config_generic(config)
+    }
+    
+    /// `new_default_generic` for `i32` symbols
+    pub fn new_default(k: usize, block_size: usize) -> Self {
+        Self::new_default_generic(k, block_size)
+    }
+    
+    /// Writes the decoded symbols to `writer` as 4 little-endian bytes each,
+    /// the layout of `split_into_blocks`, and returns the bytes written
+    ///
+    /// Blocks are written in order with the final one trimmed like in
+    /// `reassemble`. Fails with an `InvalidData` error wrapping
+    /// `DecodeError::Incomplete` if some source blocks are still missing.
+    #[cfg(feature = "std")]
+    pub fn reassemble_into_writer<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        let blocks = self.ordered_blocks().ok_or_else(|| self.incomplete_io_error())?;
+        let mut written = 0;
+        for block in blocks {
+            let bytes: Vec<u8> = block.iter().flat_map(|symbol| symbol.to_le_bytes()).collect();
+            writer.write_all(&bytes)?;
+            written += bytes.len();
+        }
+        Ok(written)
+    }
+    
+    /// Adds an encoded block after checking it against `crc`, the
+    /// `block_crc` computed by the sender
+    ///
+    /// A block that fails the check is counted in `blocks_corrupted` and
+    /// dropped before it can corrupt any source block; `None` is returned.
+    pub fn add_encoded_block_with_crc(&mut self, seed: i64, degree: usize, data: Vec<i32>, crc: u32) -> Option<usize> {
+        if block_crc(seed, degree, &data) != crc {
+            lt_warn!("dropping block with seed {}: CRC mismatch", seed);
+            self.blocks_corrupted += 1;
+            return None;
+        }
+        self.add_encoded_block(seed, degree, data)
+    }
+    
+    /// Checks the decoded blocks against the digest from `set_source_digest`
+    ///
+    /// A complete decode can still be wrong if a corrupted block slipped
+    /// through or the encoder used different parameters; this catches both.
+    /// The digest covers the decoded blocks before any `last_block_size`
+    /// trimming.
+    pub fn verify(&self) -> Result<(), IntegrityError> {
+        if !self.is_complete() {
+            return Err(IntegrityError::Incomplete { decoded: self.decoded_count(), k: self.k });
+        }
+        let expected = self.source_digest.ok_or(IntegrityError::NoDigest)?;
+        
+        let mut hasher = Sha256::new();
+        for symbol in (0..self.k).flat_map(|i| &self.decoded_blocks[&i]) {
+            hasher.update(&symbol.to_le_bytes());
+        }
+        let found = hasher.finalize();
+        
+        if found == expected {
+            Ok(())
+        } else {
+            Err(IntegrityError::DigestMismatch { expected, found })
+        }
+    }
+    
+    /// Captures the full in-progress decoder state
+    ///
+    /// The state holds the parameters, received and decoded blocks and
+    /// counters, so a decode can be paused and later resumed with `restore`.
+    /// Use `DecoderState::to_bytes` to persist it.
+    pub fn snapshot(&self) -> DecoderState {
+        // Blocks come out in index order, so equal decoders give equal states
+        let received_blocks: Vec<BlockState> = self
+            .received_blocks
+            .iter()
+            .map(|(&index, block)| {
+                let mut dependencies: Vec<usize> = block.dependencies.iter().copied().collect();
+                dependencies.sort_unstable();
+                BlockState {
+                    index,
+                    seed: block.seed,
+                    degree: block.degree,
+                    dependencies,
+                    data: block.data.clone(),
+                    eliminated: block.eliminated,
+                    constraint: block.constraint,
+                }
+            })
+            .collect();
+        
+        let mut decoded_blocks: Vec<(usize, Vec<i32>)> =
+            self.decoded_blocks.iter().map(|(&idx, data)| (idx, data.clone())).collect();
+        decoded_blocks.sort_unstable_by_key(|&(idx, _)| idx);
+        
+        let mut seen_seeds: Vec<i64> = self.seen_seeds.iter().copied().collect();
+        seen_seeds.sort_unstable();
+        
+        DecoderState {
+            k: self.k,
+            block_size: self.block_size,
+            last_block_size: self.last_block_size,
+            distribution: self.prng.distribution(),
+            random_source: self.prng.source().name().to_string(),
+            parity_count: self.precode.as_ref().map_or(0, Precode::parity_count),
+            received_blocks,
+            decoded_blocks,
+            current_round: self.current_round,
+            next_block_index: self.next_block_index,
+            blocks_wasted: self.blocks_wasted,
+            blocks_received: self.blocks_received,
+            blocks_corrupted: self.blocks_corrupted,
+            blocks_evicted: self.blocks_evicted,
+            source_digest: self.source_digest,
+            seen_seeds,
+        }
+    }
+    
+    /// Serializes the decoder state with `DecoderState::to_bytes`, so a
+    /// receiver can store it and resume with `restore_state` after a restart
+    pub fn serialize_state(&self) -> Vec<u8> {
+        self.snapshot().to_bytes()
+    }
+    
+    /// Rebuilds a decoder from bytes written by `serialize_state`
+    ///
+    /// Fails if the bytes are not a valid state, or as `restore` does.
+    pub fn restore_state(bytes: &[u8]) -> Result<Decoder, LtError> {
+        Decoder::restore(DecoderState::from_bytes(bytes)?)
+    }
+    
+    /// Rebuilds a decoder from a state produced by `snapshot`
+    ///
+    /// The restored decoder continues exactly as the original would have.
+    /// Fails if the state names an unknown random source, refers to block
+    /// indices outside the code, holds blocks of the wrong length, or has
+    /// more than `MAX_RESTORED_LEN` blocks or symbols per block, so a
+    /// corrupted state cannot make the decoder allocate without bound.
+    pub fn restore(state: DecoderState) -> Result<Decoder, LtError> {
+        let source = random_source_by_name(&state.random_source)
+            .ok_or(LtError::InvalidSnapshot("unknown random source"))?;
+        if state.k.checked_add(state.parity_count).is_none_or(|count| count > MAX_RESTORED_LEN) {
+            return Err(LtError::InvalidSnapshot("too many blocks"));
+        }
+        if state.block_size > MAX_RESTORED_LEN || state.last_block_size.is_some_and(|len| len > state.block_size) {
+            return Err(LtError::InvalidSnapshot("invalid block size"));
+        }
+        let wrong_length = |data: &[i32]| data.len() != state.block_size;
+        if state.decoded_blocks.iter().any(|(_, data)| wrong_length(data))
+            || state.received_blocks.iter().any(|block| wrong_length(&block.data))
+        {
+            return Err(LtError::InvalidSnapshot("block length differs from block size"));
+        }
+        state
+            .distribution
+            .validate(state.k)
+            .map_err(|_| LtError::InvalidSnapshot("invalid degree distribution"))?;
+        let precode = (state.parity_count > 0).then(|| Precode::with_parity_count(state.k, state.parity_count));
+        let mut decoder = Decoder::build(state.k, state.block_size, state.distribution, source, precode);
+        decoder.last_block_size = state.last_block_size;
+        decoder.current_round = state.current_round;
+        decoder.next_block_index = state.next_block_index;
+        decoder.blocks_wasted = state.blocks_wasted;
+        decoder.blocks_received = state.blocks_received;
+        decoder.blocks_corrupted = state.blocks_corrupted;
+        decoder.blocks_evicted = state.blocks_evicted;
+        decoder.source_digest = state.source_digest;
+        let symbol_count = decoder.symbol_count();
+        
+        // Decoded blocks go first so held blocks are reduced by them
+        for (idx, data) in state.decoded_blocks {
+            if idx >= symbol_count {
+                return Err(LtError::InvalidSnapshot("decoded index out of range"));
+            }
+            decoder.decoded_blocks.insert(idx, data);
+        }
+        
+        for block in state.received_blocks {
+            if block.dependencies.iter().any(|&dep| dep >= symbol_count) {
+                return Err(LtError::InvalidSnapshot("dependency index out of range"));
+            }
+            let mut received = ReceivedBlock::new(block.seed, block.degree, block.dependencies.into_iter().collect(), block.data);
+            received.eliminated = block.eliminated;
+            received.constraint = block.constraint;
+            decoder.insert_block(block.index, received);
+        }
+        
+        decoder.seen_seeds.extend(state.seen_seeds);
+        
+        // A snapshot only ever captures a settled decoder
+        decoder.pending.clear();
+        Ok(decoder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Returns the first seed from 1 whose block `pred` accepts, with its
+    /// dependencies
+    fn find_seed(decoder: &Decoder, pred: impl Fn(i64, &BTreeSet<usize>) -> bool) -> (i64, BTreeSet<usize>) {
+        (1..)
+            .map(|seed| (seed, decoder.prng.src_blocks_for_seed(seed).1))
+            .find(|(seed, deps)| pred(*seed, deps))
+            .unwrap()
+    }
+    
+    #[test]
+    fn test_decoder_initialization() {
+        let decoder: Decoder = Decoder::new_default(10, 1024);
+        assert_eq!(decoder.k, 10);
+        assert_eq!(decoder.block_size, 1024);
+        assert_eq!(decoder.decoded_count(), 0);
+    }
+    
+    #[test]
+    #[allow(unused,unused_variables,dead_code, unused_imports)]
+    fn test_add_block() {
+        let mut decoder = Decoder::new_default(2, 3);
+        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
+        
+        // With just one block of degree 1, we should be able to decode one source block
+        assert!(decoder.decoded_count() as i64 >= 0);
+    }
+    
+    #[test]
+    fn test_missing_and_decoded_indices() {
+        let mut decoder = Decoder::new_default(2, 3);
+        assert_eq!(decoder.missing_indices(), vec![0, 1]);
+        assert!(decoder.decoded_indices().is_empty());
+        
+        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
+        let decoded = decoder.decoded_indices();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoder.missing_indices(), vec![1 - decoded[0]]);
+        
+        decoder.add_encoded_block(43, 1, vec![4, 5, 6]);
+        assert!(decoder.missing_indices().is_empty());
+        assert_eq!(decoder.decoded_indices(), vec![0, 1]);
+    }
+    
+    #[test]
+    fn test_decode_complete() {
+        let mut decoder = Decoder::new_default(2, 3);
+        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
+        decoder.add_encoded_block(43, 1, vec![4, 5, 6]);
+        
+        // With two blocks of degree 1, we should be able to decode all source blocks
+        assert!(decoder.is_complete());
+    }
+    
+    #[test]
+    fn test_satisfied_block_dropped() {
+        let mut decoder = Decoder::new_default(2, 3);
+        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
+        decoder.add_encoded_block(43, 1, vec![4, 5, 6]);
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.blocks_wasted(), 0);
+        
+        // Every dependency is already decoded, so the block is not stored
+        decoder.add_encoded_block(44, 1, vec![1, 2, 3]);
+        assert!(decoder.received_blocks.is_empty());
+        assert_eq!(decoder.blocks_wasted(), 1);
+    }
+    
+    #[test]
+    fn test_drain_newly_decoded() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 30;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, -i, 3]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(21));
+        let mut decoder = Decoder::new_with_last_block_size(k, 3, 2);
+        
+        let mut emitted = vec![0; k];
+        for (seed, d, _, data) in encoder.iter().take(10 * k) {
+            decoder.add_encoded_block(seed, d, data);
+            for (index, data) in decoder.drain_newly_decoded() {
+                emitted[index] += 1;
+                let expected = &source_blocks[index][..if index == k - 1 { 2 } else { 3 }];
+                assert_eq!(data, expected);
+            }
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert!(decoder.is_complete());
+        assert_eq!(emitted, vec![1; k]);
+        assert!(decoder.drain_newly_decoded().is_empty());
+    }
+    
+    #[test]
+    fn test_set_data_len() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        // 103 symbols in blocks of 10
+        let data: Vec<i32> = (0..103).collect();
+        let mut encoder = Encoder::new_padded(data.chunks(10).map(<[i32]>::to_vec).collect(), Some(4));
+        assert_eq!(encoder.data_len(), 103);
+        
+        let mut decoder = Decoder::new_default(11, 10);
+        assert_eq!(
+            decoder.set_data_len(111),
+            Err(DecodeError::DataLenOutOfRange { data_len: 111, k: 11, block_size: 10 })
+        );
+        assert!(decoder.set_data_len(100).is_err());
+        decoder.set_data_len(encoder.data_len()).unwrap();
+        
+        assert_eq!(decoder.reassemble(), None);
+        for (seed, d, _, block) in encoder.iter().take(1000) {
+            decoder.add_encoded_block(seed, d, block);
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert_eq!(decoder.reassemble(), Some(data));
+        
+        let mut empty: Decoder = Decoder::new_default(0, 10);
+        assert!(empty.set_data_len(0).is_ok() && empty.set_data_len(1).is_err());
+    }
+    
+    #[test]
+    fn test_reassemble_into_writer() {
+        use crate::luby_transform::bytes::split_into_blocks;
+        use crate::luby_transform::encoder::Encoder;
+        
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let (source_blocks, _) = split_into_blocks(&data, 25).unwrap();
+        let mut encoder = Encoder::new_default(source_blocks, Some(6));
+        let mut decoder: Decoder = Decoder::new_default(10, 25);
+        
+        let mut out = Vec::new();
+        let err = decoder.reassemble_into_writer(&mut out).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(out.is_empty());
+        
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+        }
+        assert_eq!(decoder.reassemble_into_writer(&mut out).unwrap(), 1000);
+        assert_eq!(out, data);
+        
+        // Byte decoders write their symbols as they are
+        let mut byte_decoder: Decoder<u8> = Decoder::new_with_last_block_size_generic(2, 4, 1);
+        byte_decoder.add_encoded_block(-1, 1, vec![1, 2, 3, 4]);
+        byte_decoder.add_encoded_block(-2, 1, vec![5, 0, 0, 0]);
+        let mut out = Vec::new();
+        assert_eq!(byte_decoder.reassemble_into_writer(&mut out).unwrap(), 5);
+        assert_eq!(out, [1, 2, 3, 4, 5]);
+    }
+    
+    #[test]
+    fn test_into_decoded_blocks() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..12).map(|i| vec![i, 40 - i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(5));
+        let mut decoder = Decoder::new_with_last_block_size(12, 2, 1);
+        assert!(decoder.iter_decoded().is_none());
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+        }
+        
+        let mut expected = source_blocks;
+        expected[11].truncate(1);
+        let borrowed: Vec<&[i32]> = decoder.iter_decoded().unwrap().collect();
+        assert_eq!(borrowed, expected.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        assert_eq!(decoder.into_decoded_blocks(), Some(expected));
+        assert_eq!(Decoder::new_default(3, 2).into_decoded_blocks(), None);
+    }
+    
+    #[test]
+    fn test_add_with_indices() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        // A chain schedule no seed produces: {0}, {0, 1}, {1, 2}, ...
+        let k = 8;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 3, 100 - i]).collect();
+        let encoder = Encoder::new_default(source_blocks.clone(), Some(1));
+        let mut decoder: Decoder = Decoder::new_default(k, 2);
+        for i in (0..k).rev() {
+            let indices: Vec<usize> = (i.saturating_sub(1)..=i).collect();
+            let data = encoder.encode_indices(&indices.iter().copied().collect()).unwrap();
+            assert_eq!(decoder.add_encoded_block_with_indices(-7 - i as i64, &indices, data), Some(k - 1 - i));
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        
+        // Repeated neighbours cancel, and malformed blocks are rejected
+        let mut decoder: Decoder = Decoder::new_default(k, 2);
+        assert_eq!(decoder.add_encoded_block_with_indices(1, &[3, 5, 5], vec![9, 9]), Some(0));
+        assert_eq!(decoder.get_decoded_block(3), Some(&vec![9, 9]));
+        assert_eq!(decoder.add_encoded_block_with_indices(2, &[4, 4], vec![0, 0]), None);
+        assert_eq!(decoder.add_encoded_block_with_indices(3, &[1, k], vec![0, 0]), None);
+        assert_eq!(decoder.add_encoded_block_with_indices(4, &[1], vec![0]), None);
+        assert_eq!(decoder.blocks_corrupted(), 3);
+        assert_eq!(decoder.add_encoded_block_with_indices(1, &[6], vec![0, 0]), None);
+        assert_eq!(decoder.duplicates_received(), 1);
+    }
+    
+    #[test]
+    fn test_partial_blocks() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let mut decoder: Decoder = Decoder::new_with_last_block_size(4, 2, 1);
+        assert_eq!(decoder.get_partial_blocks(), vec![None; 4]);
+        decoder.add_encoded_block(systematic_seed(1), 1, vec![3, 4]);
+        decoder.add_encoded_block(systematic_seed(3), 1, vec![7, 0]);
+        assert_eq!(decoder.get_partial_blocks(), [None, Some(vec![3, 4]), None, Some(vec![7])]);
+        assert_eq!(decoder.get_all_decoded_blocks(), None);
+        
+        decoder.add_encoded_block(systematic_seed(0), 1, vec![1, 2]);
+        decoder.add_encoded_block(systematic_seed(2), 1, vec![5, 6]);
+        let partial: Option<Vec<Vec<i32>>> = decoder.get_partial_blocks().into_iter().collect();
+        assert_eq!(partial, decoder.get_all_decoded_blocks());
+    }
+    
+    #[test]
+    fn test_progress_accessors() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let mut decoder: Decoder = Decoder::new_default(3, 2);
+        assert_eq!((decoder.k(), decoder.block_size(), decoder.pending_count()), (3, 2, 0));
+        
+        // Blocks combining undecoded sources are buffered
+        let (seed, deps) = find_seed(&decoder, |_, deps| deps.len() == 2);
+        decoder.add_encoded_block(seed, 2, vec![0, 0]);
+        assert_eq!(decoder.pending_count(), 1);
+        assert_eq!(decoder.missing_indices(), vec![0, 1, 2]);
+        
+        // Decoding one of its sources peels it
+        let first = *deps.iter().min().unwrap();
+        decoder.add_encoded_block(systematic_seed(first), 1, vec![0, 0]);
+        assert_eq!(decoder.pending_count(), 0);
+        assert_eq!(decoder.decoded_count(), 2);
+        assert_eq!(decoder.blocks_received(), 2);
+        
+        let precoded: Decoder = Decoder::with_precode(20, 2, 0.2).unwrap();
+        assert_eq!(precoded.pending_count(), 0);
+    }
+    
+    #[test]
+    fn test_ripple_queue() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let mut decoder: Decoder = Decoder::new_default(20, 1);
+        let (seed, deps) = find_seed(&decoder, |_, deps| deps.len() >= 3);
+        let mut deps: Vec<usize> = deps.into_iter().collect();
+        deps.sort_unstable();
+        let value = deps.iter().fold(0, |acc, &idx| acc ^ (idx as i32 + 1));
+        
+        // A block with several undecoded dependencies waits outside the queue
+        decoder.add_encoded_block(seed, deps.len(), vec![value]);
+        assert!(decoder.pending.is_empty());
+        assert_eq!(decoder.received_blocks[&0].dependencies.len(), deps.len());
+        
+        // Decoding all but one of them makes it ripple
+        let (last, rest) = deps.split_last().unwrap();
+        for &idx in rest {
+            decoder.add_encoded_block(systematic_seed(idx), 1, vec![idx as i32 + 1]);
+        }
+        assert_eq!(decoder.get_decoded_block(*last), Some(&vec![*last as i32 + 1]));
+        assert!(decoder.received_blocks.is_empty());
+    }
+    
+    #[test]
+    fn test_reduced_on_arrival() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let mut decoder: Decoder = Decoder::new_default(20, 1);
+        let (seed, deps) = find_seed(&decoder, |_, deps| deps.len() >= 4);
+        let mut deps: Vec<usize> = deps.into_iter().collect();
+        deps.sort_unstable();
+        let value = deps.iter().fold(0, |acc, &idx| acc ^ (idx as i32 + 1));
+        
+        // A block arriving after one of its neighbours decoded keeps only the rest
+        let (first, rest) = deps.split_first().unwrap();
+        decoder.add_encoded_block(systematic_seed(*first), 1, vec![*first as i32 + 1]);
+        decoder.add_encoded_block(seed, deps.len(), vec![value]);
+        let block = &decoder.received_blocks[&1];
+        assert_eq!(block.degree, deps.len());
+        let mut remaining: Vec<usize> = block.dependencies.iter().copied().collect();
+        remaining.sort_unstable();
+        assert_eq!(remaining, rest);
+        assert_eq!(block.data, vec![rest.iter().fold(0, |acc, &idx| acc ^ (idx as i32 + 1))]);
+        assert!(!decoder.dependents.contains_key(first));
+        
+        // Later decodes reduce it further
+        decoder.add_encoded_block(systematic_seed(rest[0]), 1, vec![rest[0] as i32 + 1]);
+        let block = &decoder.received_blocks[&1];
+        assert_eq!(block.dependencies.len(), rest.len() - 1);
+        assert_eq!(block.data, vec![rest[1..].iter().fold(0, |acc, &idx| acc ^ (idx as i32 + 1))]);
+    }
+    
+    #[test]
+    fn test_storage_released_on_completion() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 40;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(12));
+        let mut decoder = Decoder::new_default(k, 2);
+        
+        for (seed, d, _, data) in encoder.iter().take(10 * k) {
+            decoder.add_encoded_block(seed, d, data);
+            // Held blocks always have something left to solve
+            for block in decoder.received_blocks.values() {
+                assert!(block.dependencies.iter().any(|idx| !decoder.decoded_blocks.contains_key(idx)));
+            }
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert!(decoder.is_complete());
+        assert!(decoder.received_blocks.is_empty());
+        assert!(decoder.dependents.is_empty());
+        assert!(decoder.redundant_blocks() <= decoder.blocks_received() - k);
+        
+        assert_eq!(decoder.finalize(), Ok(source_blocks));
+        
+        let incomplete: Decoder = Decoder::new_default(k, 2);
+        assert_eq!(incomplete.finalize(), Err(DecodeError::Incomplete { decoded: 0, k }));
+    }
+    
+    #[test]
+    fn test_duplicate_blocks_ignored() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..10).map(|i| vec![i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(3));
+        let (seed, d, _, data) = loop {
+            let block = encoder.generate_encoded_block(None).unwrap();
+            if block.1 >= 2 {
+                break block;
+            }
+        };
+        
+        let mut decoder = Decoder::new_default(10, 1);
+        assert_eq!(decoder.add_encoded_block(seed, d, data.clone()), Some(0));
+        assert_eq!(decoder.add_encoded_block(seed, d, data.clone()), None);
+        assert_eq!(decoder.add_encoded_block(seed, d, data), None);
+        assert_eq!(decoder.received_blocks.len(), 1);
+        assert_eq!(decoder.duplicates_received(), 2);
+        
+        // Duplicates survive a snapshot and restart
+        let restored = Decoder::restore(decoder.snapshot()).unwrap();
+        assert_eq!(restored.duplicates_received(), 2);
+        decoder.reset();
+        assert_eq!(decoder.duplicates_received(), 0);
+    }
+    
+    #[test]
+    fn test_overhead_stats() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 20;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i << 4]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(5));
+        let mut decoder = Decoder::new_default(k, 2);
+        
+        let mut last = None;
+        for (seed, d, _, data) in encoder.iter().take(500) {
+            decoder.add_encoded_block(seed, d, data.clone());
+            last = Some((seed, d, data));
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert!(decoder.is_complete());
+        
+        let ratio = decoder.overhead_ratio();
+        assert!(ratio.is_finite() && ratio >= 1.0, "ratio = {}", ratio);
+        assert_eq!(ratio, decoder.blocks_received() as f64 / k as f64);
+        assert!(decoder.redundant_blocks() <= decoder.blocks_received() - k);
+        
+        // Replaying a block counts as received and redundant
+        let redundant = decoder.redundant_blocks();
+        let (seed, d, data) = last.unwrap();
+        decoder.add_encoded_block(seed, d, data);
+        assert_eq!(decoder.redundant_blocks(), redundant + 1);
+        
+        let mut empty: Decoder = Decoder::new_default(0, 2);
+        empty.add_encoded_block(1, 1, vec![0, 0]);
+        assert_eq!(empty.overhead_ratio(), 0.0);
+    }
+    
+    #[test]
+    fn test_degree_mismatch_rejected() {
+        use crate::luby_transform::encoder::Encoder;
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let k = 10;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 3]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(4));
+        let mut decoder = Decoder::new_default(k, 1);
+        
+        let (seed, d, _, data) = encoder.generate_encoded_block(None).unwrap();
+        assert_eq!(decoder.add_encoded_block(seed, d + 1, data.clone()), None);
+        assert_eq!(decoder.add_encoded_block(systematic_seed(0), 2, vec![0]), None);
+        assert_eq!(decoder.blocks_corrupted(), 2);
+        assert_eq!(decoder.blocks_received(), 0);
+        
+        // The rejected seed is not marked as seen
+        assert!(decoder.add_encoded_block(seed, d, data.clone()).is_some());
+        
+        // The fallible variant says why
+        assert_eq!(
+            decoder.try_add_encoded_block(seed, d + 1, data.clone()),
+            Err(BlockError::DegreeMismatch { seed, declared: d + 1, expected: d })
+        );
+        assert_eq!(
+            decoder.try_add_encoded_block(seed, d, vec![1, 2]),
+            Err(BlockError::LengthMismatch { seed, expected: 1, found: 2 })
+        );
+        assert_eq!(
+            decoder.try_add_encoded_block(seed, 0, data.clone()),
+            Err(BlockError::DegreeOutOfRange { seed, degree: 0, max: k })
+        );
+        assert_eq!(decoder.try_add_encoded_block(0, 1, vec![0]), Err(BlockError::InvalidSeed { seed: 0 }));
+        assert_eq!(decoder.blocks_corrupted(), 6);
+        assert_eq!(decoder.try_add_encoded_block(seed, d, data), Ok(None));
+    }
+    
+    #[test]
+    fn test_degree_out_of_range_rejected() {
+        use crate::luby_transform::encoder::Encoder;
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let k = 5;
+        let mut decoder = Decoder::new_default(k, 2);
+        for degree in [0, k + 1] {
+            assert_eq!(decoder.add_encoded_block(7, degree, vec![1, 2]), None);
+            assert_eq!(decoder.add_encoded_block(systematic_seed(0), degree, vec![1, 2]), None);
+        }
+        assert_eq!(decoder.blocks_corrupted(), 4);
+        assert_eq!(decoder.blocks_received(), 0);
+        assert_eq!(decoder.decoded_count(), 0);
+        
+        // Every generated degree is in range
+        let mut encoder = Encoder::new_default((0..k as i32).map(|i| vec![i, -i]).collect(), Some(9));
+        for _ in 0..200 {
+            let (_, degree, indices, data) = encoder.generate_encoded_block(None).unwrap();
+            assert!((1..=k).contains(&degree) && indices.len() == degree && data.len() == 2);
+        }
+    }
+    
+    #[test]
+    fn test_wrong_length_rejected() {
+        use crate::luby_transform::encoder::Encoder;
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..6).map(|i| vec![i; 4]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(2));
+        let mut decoder = Decoder::new_with_last_block_size(6, 4, 2);
+        
+        let (seed, d, _, mut data) = encoder.generate_encoded_block(None).unwrap();
+        data.push(0);
+        assert_eq!(decoder.add_encoded_block(seed, d, data.clone()), None);
+        data.truncate(3);
+        assert_eq!(decoder.add_encoded_block(seed, d, data), None);
+        assert_eq!(decoder.blocks_corrupted(), 2);
+        
+        // Only the final source block may arrive short, and only systematically
+        assert_eq!(decoder.add_encoded_block(systematic_seed(0), 1, vec![0; 2]), None);
+        assert_eq!(decoder.blocks_corrupted(), 3);
+        assert!(decoder.add_encoded_block(systematic_seed(5), 1, vec![5; 2]).is_some());
+        assert_eq!(decoder.get_decoded_block(5), Some(&vec![5, 5, 0, 0]));
+    }
+    
+    #[test]
+    fn test_progress_callback() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 25;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i ^ 0x7f]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(9));
+        let mut decoder = Decoder::new_default(k, 2);
+        
+        let mut calls = 0;
+        let mut last = (0, 0);
+        for (seed, d, _, data) in encoder.iter().take(500) {
+            decoder.add_encoded_block_with_progress(seed, d, data, |decoded, total| {
+                assert!(decoded >= last.0);
+                calls += 1;
+                last = (decoded, total);
+            });
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        
+        assert!(decoder.is_complete());
+        assert!(calls > 0);
+        assert_eq!(last, (k, k));
+    }
+    
+    #[test]
+    fn test_try_finish_last() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 10;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * 2, i * 3]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(7));
+        
+        // Find a high-degree block to finish the decode with
+        let (seed, d, indices, data) = loop {
+            let block = encoder.generate_encoded_block(None).unwrap();
+            if block.1 >= 3 {
+                break block;
+            }
+        };
+        let missing = *indices.iter().next().unwrap();
+        
+        let mut decoder = Decoder::new_default(k, 3);
+        for i in (0..k).filter(|&i| i != missing) {
+            decoder.decoded_blocks.insert(i, source_blocks[i].clone());
+        }
+        decoder.insert_block(0, ReceivedBlock::new(seed, d, indices.clone(), data));
+        
+        assert!(decoder.try_finish_last());
+        assert_eq!(decoder.get_decoded_block(missing), Some(&source_blocks[missing]));
+        assert!(decoder.received_blocks.is_empty());
+    }
+    
+    #[test]
+    fn test_last_block_trimmed() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let block_size = 4;
+        let data: Vec<i32> = (0..23).collect();
+        let chunks: Vec<Vec<i32>> = data.chunks(block_size).map(<[i32]>::to_vec).collect();
+        let mut encoder = Encoder::new_padded(chunks, Some(17));
+        assert_eq!(encoder.last_block_size(), Some(3));
+        
+        let k = encoder.source_block_count();
+        let mut decoder = Decoder::new_with_last_block_size(k, block_size, 3);
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+        }
+        
+        let restored = Decoder::restore(decoder.snapshot()).unwrap();
+        for decoder in [decoder, restored] {
+            let decoded: Vec<i32> = decoder.get_all_decoded_blocks().unwrap().concat();
+            assert_eq!(decoded, data);
+        }
+    }
+    
+    #[test]
+    fn test_reset_keeps_distribution() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 12;
+        let mut decoder = Decoder::new_default(k, 1);
+        let cdf = decoder.prng.cdf().as_ptr();
+        
+        for round in 0..2 {
+            let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * (round + 1)]).collect();
+            let mut encoder = Encoder::new_default(source_blocks.clone(), Some(100 + round as i64));
+            while !decoder.is_complete() {
+                let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+                decoder.add_encoded_block(seed, d, block);
+            }
+            assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+            
+            decoder.reset();
+            assert_eq!(decoder.decoded_count(), 0);
+            assert_eq!(decoder.current_round(), 0);
+            assert!(decoder.received_blocks.is_empty());
+            assert_eq!(decoder.prng.cdf().as_ptr(), cdf);
+        }
+    }
+    
+    #[test]
+    fn test_decode_log() {
+        let mut prng = PRNG::new_default(2);
+        let (_, _, first) = prng.get_src_blocks(Some(42));
+        let (_, _, second) = prng.get_src_blocks(Some(43));
+        let first = *first.iter().next().unwrap();
+        let second = *second.iter().next().unwrap();
+        
+        let mut decoder = Decoder::new_default(2, 3);
+        decoder.add_encoded_block(42, 1, vec![1, 2, 3]);
+        decoder.add_encoded_block(43, 1, vec![4, 5, 6]);
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.decode_log(), &[(42, first), (43, second)]);
+    }
+    
+    #[test]
+    fn test_gaussian_fallback() {
+        let k = 3;
+        let source_blocks = [vec![1, 2], vec![3, 4], vec![5, 6]];
+        
+        // Find seeds for {0, 1, 2} and two distinct pairs: no block has degree 1,
+        // so peeling alone stalls, yet the system is fully determined
+        let mut decoder = Decoder::new_default(k, 2);
+        let triple = find_seed(&decoder, |_, deps| deps.len() == 3);
+        let pair = find_seed(&decoder, |_, deps| deps.len() == 2);
+        let other = find_seed(&decoder, |_, deps| deps.len() == 2 && *deps != pair.1);
+        let picked = [triple, pair, other];
+        
+        let encode = |deps: &BTreeSet<usize>| {
+            deps.iter().fold(vec![0, 0], |acc, &i| {
+                acc.iter().zip(&source_blocks[i]).map(|(a, b)| a ^ b).collect()
+            })
+        };
+        
+        // Two blocks are not enough for the automatic fallback
+        for (seed, deps) in &picked[..2] {
+            decoder.add_encoded_block(*seed, deps.len(), encode(deps));
+        }
+        assert_eq!(decoder.decoded_count(), 0);
+        
+        // The third makes the system solvable and elimination completes it
+        let (seed, deps) = &picked[2];
+        decoder.add_encoded_block(*seed, deps.len(), encode(deps));
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.to_vec()));
+        assert!(decoder.received_blocks.is_empty());
+        
+        // Pure peeling stalls on the same blocks until asked to solve
+        let mut peeling = Decoder::new_default(k, 2);
+        peeling.set_gaussian_fallback(false);
+        assert!(!peeling.gaussian_fallback());
+        for (seed, deps) in &picked {
+            peeling.add_encoded_block(*seed, deps.len(), encode(deps));
+        }
+        assert_eq!(peeling.decoded_count(), 0);
+        assert!(peeling.force_solve());
+        assert_eq!(peeling.get_all_decoded_blocks(), Some(source_blocks.to_vec()));
+    }
+    
+    #[test]
+    fn test_graph_snapshot() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let k = 6;
+        let wanted: [BTreeSet<usize>; 3] = [[0, 1].into(), [2, 3].into(), [3, 4].into()];
+        let mut decoder = Decoder::new_default(k, 1);
+        for wanted in &wanted {
+            let (seed, _) = find_seed(&decoder, |_, deps| deps == wanted);
+            decoder.add_encoded_block(seed, 2, vec![0]);
+        }
+        assert_eq!(decoder.graph_snapshot(), vec![(0, vec![0, 1]), (1, vec![2, 3]), (2, vec![3, 4])]);
+        
+        // Decoding 0 peels 1 and frees the first block
+        decoder.add_encoded_block(systematic_seed(0), 1, vec![0]);
+        assert_eq!(decoder.graph_snapshot(), vec![(1, vec![2, 3]), (2, vec![3, 4])]);
+        assert_eq!(decoder.decoded_indices(), vec![0, 1]);
+        
+        // Decoding 2 peels 3 and then 4
+        decoder.add_encoded_block(systematic_seed(2), 1, vec![0]);
+        assert!(decoder.graph_snapshot().is_empty());
+        assert_eq!(decoder.decoded_indices(), vec![0, 1, 2, 3, 4]);
+    }
+    
+    #[test]
+    fn test_add_blocks_batch() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 30;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i << 8]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(12));
+        let mut blocks: Vec<(i64, usize, Vec<i32>)> = (0..3 * k)
+            .map(|_| {
+                let (seed, degree, _, data) = encoder.generate_encoded_block(None).unwrap();
+                (seed, degree, data)
+            })
+            .collect();
+        blocks.push(blocks[5].clone());
+        
+        let mut incremental = Decoder::new_default(k, 2);
+        let one_by_one: Vec<Option<usize>> = blocks
+            .iter()
+            .map(|(seed, degree, data)| incremental.add_encoded_block(*seed, *degree, data.clone()))
+            .collect();
+        
+        let mut batch = Decoder::new_default(k, 2);
+        assert_eq!(batch.add_blocks_batch(blocks), one_by_one);
+        assert_eq!(one_by_one.last(), Some(&None));
+        
+        assert_eq!(batch.get_all_decoded_blocks(), Some(source_blocks));
+        assert_eq!(batch.get_all_decoded_blocks(), incremental.get_all_decoded_blocks());
+        assert!(batch.current_round() < incremental.current_round());
+    }
+    
+    #[test]
+    fn test_max_rounds() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 30;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 9]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(12));
+        let blocks: Vec<(i64, usize, Vec<i32>)> = (0..3 * k)
+            .map(|_| {
+                let (seed, degree, _, data) = encoder.generate_encoded_block(None).unwrap();
+                (seed, degree, data)
+            })
+            .collect();
+        
+        let mut unbounded = Decoder::new_default(k, 1);
+        assert_eq!(unbounded.max_rounds(), 2 * k);
+        unbounded.add_blocks_batch(blocks.clone());
+        assert!(unbounded.is_complete() && !unbounded.round_budget_exhausted());
+        
+        let mut decoder = Decoder::new_default(k, 1);
+        decoder.set_max_rounds(1);
+        decoder.add_blocks_batch(blocks);
+        assert!(decoder.round_budget_exhausted());
+        assert!(!decoder.is_complete());
+        assert_eq!(decoder.current_round(), 1);
+        
+        // The queue survives, so a bigger budget picks up where it stopped
+        decoder.set_max_rounds(100);
+        assert!(decoder.resume());
+        assert!(!decoder.round_budget_exhausted());
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+    }
+    
+    #[test]
+    fn test_deterministic_order() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 50;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i ^ 0x55]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(21));
+        let blocks: Vec<(i64, usize, Vec<i32>)> = (0..2 * k)
+            .map(|_| {
+                let (seed, degree, _, data) = encoder.generate_encoded_block(None).unwrap();
+                (seed, degree, data)
+            })
+            .collect();
+        
+        // Separate decoders fed the same blocks solve in the same order
+        let run = || {
+            let mut decoder = Decoder::new_default(k, 1);
+            for (seed, degree, data) in blocks.iter().cloned() {
+                decoder.add_encoded_block(seed, degree, data);
+            }
+            assert!(decoder.is_complete());
+            (decoder.decode_log().to_vec(), decoder.current_round())
+        };
+        let first = run();
+        for _ in 0..4 {
+            assert_eq!(run(), first);
+        }
+    }
+    
+    #[test]
+    fn test_buffer_limit() {
+        use crate::luby_transform::encoder::Encoder;
+        use crate::luby_transform::prng::SplitMix;
+        
+        let k = 50;
+        let probe: Decoder = Decoder::new_default(k, 2);
+        let blocks: Vec<(i64, usize)> = (1..)
+            .map(|seed| (seed, probe.prng.src_blocks_for_seed(seed).1.len()))
+            .filter(|&(_, degree)| degree >= 2)
+            .take(6)
+            .collect();
+        let held = |policy| {
+            let mut decoder = Decoder::new_default(k, 2);
+            decoder.set_max_buffered_packets(3, policy);
+            for &(seed, degree) in &blocks {
+                decoder.add_encoded_block(seed, degree, vec![0, 0]);
+            }
+            assert_eq!((decoder.pending_count(), decoder.blocks_evicted()), (3, 3));
+            let seeds: Vec<i64> = decoder.received_blocks.values().map(|block| block.seed).collect();
+            (decoder, seeds)
+        };
+        
+        let (_, seeds) = held(EvictionPolicy::RejectNew);
+        assert_eq!(seeds, [blocks[0].0, blocks[1].0, blocks[2].0]);
+        let (mut decoder, seeds) = held(EvictionPolicy::EvictOldest);
+        assert_eq!(seeds, [blocks[3].0, blocks[4].0, blocks[5].0]);
+        assert_eq!(decoder.max_buffered_packets(), Some(3));
+        
+        // Evicted seeds stay seen and count as redundant
+        decoder.add_encoded_block(blocks[0].0, blocks[0].1, vec![0, 0]);
+        assert_eq!(decoder.duplicates_received(), 1);
+        assert_eq!(decoder.redundant_blocks(), 4);
+        let restored = Decoder::restore(decoder.snapshot()).unwrap();
+        assert_eq!(restored.blocks_evicted(), 3);
+        
+        // A memory limit is converted to whole blocks
+        decoder.set_max_memory_bytes(2 * 2 * core::mem::size_of::<i32>() + 1, EvictionPolicy::EvictOldest);
+        assert_eq!(decoder.max_buffered_packets(), Some(2));
+        assert_eq!(decoder.pending_count(), 2);
+        decoder.clear_max_buffered_packets();
+        assert_eq!(decoder.max_buffered_packets(), None);
+        
+        let (decoder, seeds) = held(EvictionPolicy::EvictHighestDegree);
+        let max_held = decoder.received_blocks.values().map(|block| block.degree).max().unwrap();
+        for &(seed, degree) in &blocks {
+            if !seeds.contains(&seed) {
+                assert!(degree >= max_held);
+            }
+        }
+        
+        // A full stream still decodes under every policy, since blocks that
+        // can be peeled are always admitted
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, -i]).collect();
+        for policy in [EvictionPolicy::RejectNew, EvictionPolicy::EvictOldest, EvictionPolicy::EvictHighestDegree] {
+            let mut encoder = Encoder::new_default(source_blocks.clone(), Some(3));
+            let mut decoder = Decoder::new_default(k, 2);
+            decoder.set_max_buffered_packets(10, policy);
+            for (seed, d, _, data) in encoder.iter().take(2000) {
+                decoder.add_encoded_block(seed, d, data);
+                assert!(decoder.pending_count() <= 10);
+                assert_eq!(decoder.pending_count(), decoder.received_blocks.len());
+                if decoder.is_complete() {
+                    break;
+                }
+            }
+            assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()), "{:?}", policy);
+        }
+        
+        // Seed 0 is a real block for generators that allow it
+        let mut decoder: Decoder = Decoder::with_source(k, 2, DegreeDistribution::default(), Box::new(SplitMix::default())).unwrap();
+        let degree = decoder.prng.src_blocks_for_seed(0).1.len();
+        assert!(degree >= 2);
+        decoder.set_max_buffered_packets(1, EvictionPolicy::EvictOldest);
+        decoder.add_encoded_block(0, degree, vec![0, 0]);
+        assert_eq!(decoder.pending_count(), 1);
+        let (seed, deps) = find_seed(&decoder, |_, deps| deps.len() >= 2);
+        decoder.add_encoded_block(seed, deps.len(), vec![0, 0]);
+        assert_eq!((decoder.pending_count(), decoder.blocks_evicted()), (1, 1));
+        assert_eq!(decoder.received_blocks.values().next().unwrap().seed, seed);
+    }
+    
+    #[test]
+    fn test_observer() {
+        use crate::luby_transform::encoder::Encoder;
+        use std::sync::{Arc, Mutex};
+        
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Decoded(usize, Vec<i32>),
+            Complete,
+            Stall,
+        }
+        struct Recorder(Arc<Mutex<Vec<Event>>>);
+        impl DecodeObserver<i32> for Recorder {
+            fn on_block_decoded(&mut self, index: usize, data: &[i32]) {
+                self.0.lock().unwrap().push(Event::Decoded(index, data.to_vec()));
+            }
+            fn on_complete(&mut self) {
+                self.0.lock().unwrap().push(Event::Complete);
+            }
+            fn on_stall(&mut self) {
+                self.0.lock().unwrap().push(Event::Stall);
+            }
+        }
+        
+        let k = 10;
+        let mut source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i + 1, i + 2]).collect();
+        source_blocks[k - 1][2] = 0;
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(4));
+        let mut decoder = Decoder::new_with_last_block_size(k, 3, 2);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        decoder.set_observer(Recorder(Arc::clone(&events)));
+        while !decoder.is_complete() {
+            let (seed, d, _, data) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, data);
+        }
+        
+        // Every source block is reported once, trimmed, then completion
+        let mut events = core::mem::take(&mut *events.lock().unwrap());
+        assert_eq!(events.pop(), Some(Event::Complete));
+        let mut decoded: Vec<(usize, Vec<i32>)> = events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Decoded(index, data) => Some((index, data)),
+                _ => None,
+            })
+            .collect();
+        decoded.sort_unstable();
+        source_blocks[k - 1].truncate(2);
+        assert_eq!(decoded, source_blocks.into_iter().enumerate().collect::<Vec<_>>());
+        assert!(decoder.take_observer().is_some());
+        
+        // Pure peeling stuck on as many blocks as are missing reports a stall
+        let mut decoder: Decoder = Decoder::new_default(3, 1);
+        decoder.set_gaussian_fallback(false);
+        let stalls = Arc::new(Mutex::new(Vec::new()));
+        decoder.set_observer(Recorder(Arc::clone(&stalls)));
+        let mut seen: Vec<BTreeSet<usize>> = Vec::new();
+        for _ in 0..3 {
+            let (seed, deps) = find_seed(&decoder, |_, deps| deps.len() >= 2 && !seen.contains(deps));
+            decoder.add_encoded_block(seed, deps.len(), vec![1]);
+            seen.push(deps);
+        }
+        assert_eq!(*stalls.lock().unwrap(), [Event::Stall]);
+    }
+    
+    #[test]
+    fn test_add_result() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let k = 4;
+        let chain: [BTreeSet<usize>; 3] = [[0, 1].into(), [1, 2].into(), [2, 3].into()];
+        let mut decoder = Decoder::new_default(k, 1);
+        for link in &chain {
+            let (seed, _) = find_seed(&decoder, |_, deps| deps == link);
+            let result = decoder.add_encoded_block_report(seed, 2, vec![0]);
+            assert_eq!((result.newly_decoded, result.complete), (0, false));
+        }
+        
+        // The bootstrapping degree-1 block peels the whole chain
+        let result = decoder.add_encoded_block_report(systematic_seed(0), 1, vec![0]);
+        assert_eq!(result, AddResult { block_index: Some(3), newly_decoded: 4, complete: true });
+        
+        let duplicate = decoder.add_encoded_block_report(systematic_seed(0), 1, vec![0]);
+        assert_eq!(duplicate, AddResult { block_index: None, newly_decoded: 0, complete: true });
+    }
+    
+    #[test]
+    fn test_is_stalled() {
+        use crate::luby_transform::prng::systematic_seed;
+        
+        // Two distinct seeds that both combine source blocks 0 and 1
+        let k = 4;
+        let pair: BTreeSet<usize> = [0, 1].into();
+        let mut decoder = Decoder::new_default(k, 1);
+        let (first, _) = find_seed(&decoder, |_, deps| *deps == pair);
+        let (second, _) = find_seed(&decoder, |seed, deps| *deps == pair && seed != first);
+        let seeds = [first, second];
+        
+        assert!(decoder.is_stalled());
+        for &seed in &seeds {
+            decoder.add_encoded_block(seed, 2, vec![3]);
+        }
+        assert_eq!(decoder.reducible_blocks(), 0);
+        assert!(decoder.is_stalled());
+        
+        // Decoding one endpoint unblocks the other
+        decoder.add_encoded_block(systematic_seed(0), 1, vec![1]);
+        assert_eq!(decoder.decoded_indices(), vec![0, 1]);
+        assert_eq!(decoder.get_decoded_block(1), Some(&vec![2]));
+        assert!(decoder.is_stalled());
+        
+        decoder.add_encoded_block(systematic_seed(2), 1, vec![0]);
+        decoder.add_encoded_block(systematic_seed(3), 1, vec![0]);
+        assert!(decoder.is_complete() && !decoder.is_stalled());
+    }
+    
+    #[test]
+    fn test_verify() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 16;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * 31, -i]).collect();
+        let decode = |tamper: bool| {
+            let mut encoder = Encoder::new_default(source_blocks.clone(), Some(7));
+            let mut decoder = Decoder::new_default(k, 3);
+            assert_eq!(decoder.verify(), Err(IntegrityError::Incomplete { decoded: 0, k }));
+            decoder.set_source_digest(encoder.source_digest());
+            
+            // The first degree-1 block always decodes its index directly
+            let mut tampered = !tamper;
+            while !decoder.is_complete() {
+                let (seed, degree, _, mut data) = encoder.generate_encoded_block(None).unwrap();
+                if degree == 1 && !tampered {
+                    data[1] ^= 0x100;
+                    tampered = true;
+                }
+                decoder.add_encoded_block(seed, degree, data);
+            }
+            decoder
+        };
+        
+        assert_eq!(decode(false).verify(), Ok(()));
+        
+        let decoder = decode(true);
+        assert!(decoder.is_complete());
+        assert_ne!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+        assert!(matches!(decoder.verify(), Err(IntegrityError::DigestMismatch { .. })));
+        
+        // The digest survives a snapshot round trip
+        let restored = Decoder::restore(DecoderState::from_bytes(&decoder.snapshot().to_bytes()).unwrap()).unwrap();
+        assert_eq!(restored.verify(), decoder.verify());
+        
+        let mut unset = Decoder::new_default(1, 1);
+        unset.add_encoded_block(crate::luby_transform::prng::systematic_seed(0), 1, vec![5]);
+        assert_eq!(unset.verify(), Err(IntegrityError::NoDigest));
+    }
+    
+    #[test]
+    fn test_indexed_peeling_large_k() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 1000;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i ^ 0x5a5a]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(2024));
+        let mut decoder = Decoder::new_default(k, 2);
+        
+        let mut received = 0;
+        while !decoder.is_complete() {
+            let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, block);
+            received += 1;
+            assert!(received < 2 * k, "decode did not converge");
+        }
+        
+        // Every round either examines a new block or follows fresh progress
+        assert!(decoder.current_round() <= received + k);
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        
+        // The dependency index only references blocks that are still held
+        for blocks in decoder.dependents.values() {
+            assert!(blocks.iter().all(|idx| decoder.received_blocks.contains_key(idx)));
+        }
+    }
+    
+    #[test]
+    fn test_snapshot_restore() {
+        use crate::luby_transform::encoder::Encoder;
+        
+        let k = 30;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, -i, i << 8]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(11));
+        let blocks: Vec<_> = (0..200).map(|_| encoder.generate_encoded_block(None).unwrap()).collect();
+        
+        let mut uninterrupted = Decoder::new_default(k, 3);
+        let mut first_half = Decoder::new_default(k, 3);
+        for (seed, d, _, data) in &blocks[..20] {
+            uninterrupted.add_encoded_block(*seed, *d, data.clone());
+            first_half.add_encoded_block(*seed, *d, data.clone());
+        }
+        
+        let state = first_half.snapshot();
+        assert_eq!(DecoderState::from_bytes(&state.to_bytes()), Ok(state.clone()));
+        let mut resumed = Decoder::restore(state).unwrap();
+        assert_eq!(resumed.snapshot(), first_half.snapshot());
+        
+        // The byte form survives a restart the same way
+        let bytes = first_half.serialize_state();
+        assert_eq!(Decoder::restore_state(&bytes).unwrap().snapshot(), first_half.snapshot());
+        assert!(Decoder::restore_state(&bytes[..bytes.len() - 1]).is_err());
+        
+        // And through any serde format
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&first_half.snapshot()).unwrap();
+            let state: DecoderState = serde_json::from_str(&json).unwrap();
+            assert_eq!(Decoder::restore(state).unwrap().snapshot(), first_half.snapshot());
+        }
+        
+        for (seed, d, _, data) in &blocks[20..] {
+            uninterrupted.add_encoded_block(*seed, *d, data.clone());
+            resumed.add_encoded_block(*seed, *d, data.clone());
+        }
+        assert!(uninterrupted.is_complete());
+        assert_eq!(resumed.get_all_decoded_blocks(), uninterrupted.get_all_decoded_blocks());
+        assert_eq!(resumed.get_all_decoded_blocks(), Some(source_blocks));
+    }
+    
+    #[test]
+    fn test_splitmix_round_trip() {
+        use crate::luby_transform::encoder::Encoder;
+        use crate::luby_transform::prng::SplitMix;
+        
+        let k = 40;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 3, !i]).collect();
+        let distribution = DegreeDistribution::default();
+        let mut encoder = Encoder::with_source(source_blocks.clone(), distribution, Box::new(SplitMix::default())).unwrap();
+        encoder.reset_seed(0x1234_5678_9abc);
+        let mut decoder = Decoder::with_source(k, 2, distribution, Box::new(SplitMix::default())).unwrap();
+        
+        for (i, (seed, d, _, data)) in encoder.iter().take(400).enumerate() {
+            assert!(seed >= 0);
+            decoder.add_encoded_block(seed, d, data);
+            if i == 10 {
+                // The generator survives a snapshot round trip
+                decoder = Decoder::restore(decoder.snapshot()).unwrap();
+                assert_eq!(decoder.prng.source().name(), "splitmix");
+            }
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+    }
+    
+    #[test]
+    fn test_restore_rejects_malformed() {
+        let snapshot = Decoder::new_default(4, 2).snapshot().to_bytes();
+        assert_eq!(
+            DecoderState::from_bytes(&snapshot[..snapshot.len() - 1]).err(),
+            Some(LtError::InvalidSnapshot("truncated"))
+        );
+        assert_eq!(
+            DecoderState::from_bytes(b"nope").err(),
+            Some(LtError::InvalidSnapshot("bad magic"))
+        );
+        
+        let mut state = Decoder::new_default(4, 2).snapshot();
+        state.random_source = "nope".to_string();
+        assert_eq!(
+            Decoder::restore(state).err(),
+            Some(LtError::InvalidSnapshot("unknown random source"))
+        );
+        
+        let mut state = Decoder::new_default(4, 2).snapshot();
+        state.k = MAX_RESTORED_LEN + 1;
+        assert_eq!(Decoder::restore(state).err(), Some(LtError::InvalidSnapshot("too many blocks")));
+        
+        let mut state = Decoder::new_default(4, 2).snapshot();
+        state.parity_count = usize::MAX;
+        assert_eq!(Decoder::restore(state).err(), Some(LtError::InvalidSnapshot("too many blocks")));
+        
+        let mut state = Decoder::new_default(4, 2).snapshot();
+        state.block_size = MAX_RESTORED_LEN + 1;
+        assert_eq!(Decoder::restore(state).err(), Some(LtError::InvalidSnapshot("invalid block size")));
+        
+        let mut state = Decoder::new_default(4, 2).snapshot();
+        state.decoded_blocks.push((0, vec![1]));
+        assert_eq!(
+            Decoder::restore(state).err(),
+            Some(LtError::InvalidSnapshot("block length differs from block size"))
+        );
     }
 }
\ No newline at end of file
@@ -1,40 +1,364 @@
-use super::prng::PRNG;
-use std::collections::HashSet;
+use super::error::{DistError, EncodeError};
+use super::precode::Precode;
+use super::xor::{xor_into, XorElement};
+use super::sha256;
+use super::codec_id::CodecId;
+use super::config::LtConfig;
+use super::prng::{systematic_seed, DegreeDistribution, DEFAULT_SEED, DistributionTable, Minstd, RandomSource, SeedSequence, PRNG};
+use alloc::collections::BTreeSet;
+#[cfg(not(feature = "std"))]
+use super::compat::{vec, Box, Vec};
+use alloc::sync::Arc;
+
+/// An encoded block as (seed, degree, source indices, data), see
+/// `Encoder::generate_encoded_block`
+pub type GeneratedBlock<T = i32> = (i64, usize, BTreeSet<usize>, Vec<T>);
 
 /// Encoder for Luby Transform codes
 /// 
 /// This encoder is responsible for generating encoded blocks from the source data blocks
 /// using the Luby Transform algorithm.
-pub struct Encoder {
-    source_blocks: Vec<Vec<i32>>,
+///
+/// Blocks hold `i32` symbols unless another `XorElement` is chosen, e.g.
+/// `Encoder<u8>` for byte payloads. The plain constructors build `i32`
+/// encoders; their `_generic` counterparts take any symbol type.
+pub struct Encoder<T: XorElement = i32> {
+    source_blocks: Arc<Vec<Vec<T>>>, // Shared with other encoders until modified
     prng: PRNG,
     k: usize,
+    original_lengths: Vec<usize>,
+    systematic: bool,
+    systematic_sent: usize,
+    precode: Option<Precode>,
+    covered: Option<Vec<bool>>, // Source indices already sent as degree-1 blocks, when coverage is enabled
+    acknowledged: Option<Vec<bool>>, // Source indices the receiver reported decoded, see `apply_feedback`
+    seeds: SeedSequence,        // Per-index seeds for `generate_block_at`
+    packets_generated: usize,
 }
 
-impl Encoder {
+impl<T: XorElement> Encoder<T> {
     /// Creates a new Encoder with the given source blocks
-    pub fn new(source_blocks: Vec<Vec<i32>>, delta: f64, c: f64) -> Self {
+    ///
+    /// Fails if the RSD parameters are invalid for the block count, see
+    /// `validate_params`.
+    pub fn new_generic(source_blocks: Vec<Vec<T>>, delta: f64, c: f64) -> Result<Self, DistError> {
+        Self::with_distribution_generic(source_blocks, DegreeDistribution::Robust { delta, c })
+    }
+    
+    /// Creates a new Encoder sampling degrees from `distribution`
+    pub fn with_distribution_generic(source_blocks: Vec<Vec<T>>, distribution: DegreeDistribution) -> Result<Self, DistError> {
+        Self::with_source_generic(source_blocks, distribution, Box::new(Minstd::default()))
+    }
+    
+    /// Creates a new Encoder drawing its random numbers from `source`
+    ///
+    /// The decoder must be built with the same kind of generator, see
+    /// `Decoder::with_source`.
+    pub fn with_source_generic(source_blocks: Vec<Vec<T>>, distribution: DegreeDistribution, source: Box<dyn RandomSource>) -> Result<Self, DistError> {
+        let prng = PRNG::with_source(source_blocks.len(), distribution, source)?;
+        Ok(Self::from_prng(source_blocks, prng))
+    }
+    
+    /// Creates a new Encoder sampling degrees from a shared table
+    ///
+    /// The table must have been built for `source_blocks.len()` blocks.
+    pub fn with_table_generic(source_blocks: Vec<Vec<T>>, table: Arc<DistributionTable>) -> Result<Self, EncodeError> {
+        if table.k() != source_blocks.len() {
+            return Err(EncodeError::TableMismatch { table_k: table.k(), k: source_blocks.len() });
+        }
+        Ok(Self::from_prng(source_blocks, PRNG::with_table(table)))
+    }
+    
+    /// Creates a new Encoder with default parameters
+    ///
+    /// Without a seed the running stream starts from `DEFAULT_SEED`.
+    pub fn new_default_generic(source_blocks: Vec<Vec<T>>, seed: Option<i64>) -> Self {
+        Self::with_shared_source(Arc::new(source_blocks), seed)
+    }
+    
+    /// Creates a new Encoder with default parameters over source blocks
+    /// shared with other owners
+    ///
+    /// The blocks are not copied, so several encoders, e.g. one per thread,
+    /// can generate from one copy of a large source. An encoder only copies
+    /// the blocks if it is asked to modify them, see `push_source_block`.
+    pub fn with_shared_source(source_blocks: Arc<Vec<Vec<T>>>, seed: Option<i64>) -> Self {
+        let mut prng = PRNG::new_default(source_blocks.len());
+        prng.set_seed(seed.unwrap_or(DEFAULT_SEED));
+        Self::from_prng(source_blocks, prng)
+    }
+    
+    fn from_prng(source_blocks: impl Into<Arc<Vec<Vec<T>>>>, prng: PRNG) -> Self {
+        let source_blocks = source_blocks.into();
         let k = source_blocks.len();
-        let prng = PRNG::new(k, delta, c);
+        let original_lengths = source_blocks.iter().map(Vec::len).collect();
+        let seeds = SeedSequence::new(prng.source().seed().unwrap_or(0));
         
         Self {
             source_blocks,
             prng,
             k,
+            original_lengths,
+            systematic: false,
+            systematic_sent: 0,
+            precode: None,
+            covered: None,
+            acknowledged: None,
+            seeds,
+            packets_generated: 0,
         }
     }
     
-    /// Creates a new Encoder with default parameters
-    pub fn new_default(source_blocks: Vec<Vec<i32>>, seed: Option<i64>) -> Self {
+    /// Creates a new Encoder from a validated `LtConfig`, seeded with its seed
+    /// if it has one
+    ///
+    /// Blocks shorter than `config.block_size()` are zero-padded, keeping
+    /// their lengths in `original_lengths` like `new_padded`. Fails if there
+    /// are not `config.k()` blocks or if one is longer than the block size.
+    pub fn from_config(mut source_blocks: Vec<Vec<T>>, config: &LtConfig) -> Result<Self, EncodeError> {
+        if source_blocks.len() != config.k() {
+            return Err(EncodeError::SourceCountChanged { expected: config.k(), found: source_blocks.len() });
+        }
+        let original_lengths: Vec<usize> = source_blocks.iter().map(Vec::len).collect();
+        if let Some(index) = original_lengths.iter().position(|&len| len > config.block_size()) {
+            return Err(EncodeError::MismatchedBlockLength {
+                index,
+                expected: config.block_size(),
+                found: original_lengths[index],
+            });
+        }
+        for block in &mut source_blocks {
+            block.resize(config.block_size(), T::ZERO);
+        }
+        
+        let prng = PRNG::with_table(config.table());
+        let mut encoder = Self::from_prng(source_blocks, prng);
+        encoder.original_lengths = original_lengths;
+        encoder.systematic = config.systematic();
+        if let Some(seed) = config.seed() {
+            encoder.reset_seed(seed);
+        }
+        Ok(encoder)
+    }
+    
+    /// Creates a new Encoder with default parameters, zero-padding every
+    /// source block to the length of the longest one
+    ///
+    /// The unpadded lengths are kept in `original_lengths` so decoded blocks
+    /// can be trimmed back to their real size.
+    pub fn new_padded_generic(mut source_blocks: Vec<Vec<T>>, seed: Option<i64>) -> Self {
+        let original_lengths: Vec<usize> = source_blocks.iter().map(Vec::len).collect();
+        let max_len = original_lengths.iter().copied().max().unwrap_or(0);
+        for block in &mut source_blocks {
+            block.resize(max_len, T::ZERO);
+        }
+        
+        let mut encoder = Self::new_default_generic(source_blocks, seed);
+        encoder.original_lengths = original_lengths;
+        encoder
+    }
+    
+    /// Creates a new systematic Encoder with default parameters
+    ///
+    /// The first `k` calls to `generate_encoded_block` without an explicit
+    /// seed emit the source blocks verbatim, in order, as degree-1 blocks
+    /// under the reserved seeds from `systematic_seed`. Later calls produce
+    /// regular LT blocks.
+    pub fn new_systematic_generic(source_blocks: Vec<Vec<T>>, seed: Option<i64>) -> Self {
+        let mut encoder = Self::new_default_generic(source_blocks, seed);
+        encoder.set_systematic(true);
+        encoder
+    }
+    
+    /// Switches the systematic prefix of `new_systematic` on or off for an
+    /// encoder built any other way
+    ///
+    /// Enabling it restarts the prefix, so the next `k` blocks are the
+    /// source blocks verbatim even if some were sent before.
+    pub fn set_systematic(&mut self, enabled: bool) {
+        self.systematic = enabled;
+        self.systematic_sent = 0;
+    }
+    
+    /// Checks whether the encoder emits the systematic prefix
+    pub fn is_systematic(&self) -> bool {
+        self.systematic
+    }
+    
+    /// Creates a new Encoder that applies a sparse LDPC-style precode first
+    ///
+    /// `ceil(redundancy * k)` parity blocks are appended to the source blocks
+    /// and the LT layer encodes the expanded set. Decode with
+    /// `Decoder::with_precode` using the same `k` and `redundancy`.
+    pub fn with_precode_generic(source_blocks: Vec<Vec<T>>, redundancy: f64, seed: Option<i64>) -> Result<Self, EncodeError> {
         let k = source_blocks.len();
-        let mut prng = PRNG::new_default(k);
-        prng.set_seed(seed.unwrap_or(0));
+        if k == 0 {
+            return Err(EncodeError::NoSourceBlocks);
+        }
+        let original_lengths = source_blocks.iter().map(Vec::len).collect();
+        let precode = Precode::new(k, redundancy)?;
+        let expanded = precode.expand(&source_blocks)?;
         
-        Self {
-            source_blocks,
-            prng,
-            k,
+        let mut encoder = Self::new_default_generic(expanded, seed);
+        encoder.k = k;
+        encoder.original_lengths = original_lengths;
+        encoder.precode = Some(precode);
+        Ok(encoder)
+    }
+    
+    /// Guarantees a degree-1 block for every source index among the first
+    /// `k` generated blocks
+    ///
+    /// With small `k`, Robust Soliton sampling often yields no degree-1 block
+    /// for a while and peeling cannot start. While enabled, each of the first
+    /// `k` calls to `generate_encoded_block` without an explicit seed keeps a
+    /// sampled degree-1 block for an uncovered index, and otherwise emits the
+    /// lowest uncovered source block under its `systematic_seed`, which the
+    /// decoder accepts directly. Regular sampling resumes once every index is
+    /// covered.
+    pub fn set_degree_one_coverage(&mut self, enabled: bool) {
+        self.covered = enabled.then(|| vec![false; self.k]);
+    }
+    
+    /// Applies a bitmap of decoded source blocks from `Decoder::feedback`
+    ///
+    /// Later calls to `generate_encoded_block` without an explicit seed skip
+    /// seeds whose blocks only combine source blocks the receiver already
+    /// has, so every block sent carries something missing. Only the choice
+    /// of seeds changes, so any decoder for the code still accepts the
+    /// blocks. At most 64 seeds are drawn per block, which bounds the
+    /// cost once nearly everything is acknowledged. Each call
+    /// replaces the previous feedback.
+    ///
+    /// Fails unless the bitmap has `k.div_ceil(8)` bytes.
+    pub fn apply_feedback(&mut self, feedback: &[u8]) -> Result<(), EncodeError> {
+        let expected = self.k.div_ceil(8);
+        if feedback.len() != expected {
+            return Err(EncodeError::FeedbackLength { expected, found: feedback.len() });
         }
+        self.acknowledged = Some((0..self.k).map(|i| feedback[i / 8] & (1 << (i % 8)) != 0).collect());
+        Ok(())
+    }
+    
+    /// Forgets the feedback given to `apply_feedback`
+    pub fn clear_feedback(&mut self) {
+        self.acknowledged = None;
+    }
+    
+    /// Re-seeds the PRNG without rebuilding the degree distribution
+    ///
+    /// A systematic encoder starts over with its verbatim prefix, and degree-1
+    /// coverage starts over as well. `seed` also becomes the root of the
+    /// `SeedSequence` used by `generate_block_at`.
+    pub fn reset_seed(&mut self, seed: i64) {
+        self.prng.set_seed(seed);
+        self.seeds = SeedSequence::new(seed);
+        self.systematic_sent = 0;
+        if let Some(covered) = &mut self.covered {
+            covered.fill(false);
+        }
+    }
+    
+    /// Replaces the source blocks, keeping the degree distribution
+    ///
+    /// The block count must stay the same since the distribution depends on it.
+    pub fn set_source_blocks(&mut self, source_blocks: Vec<Vec<T>>) -> Result<(), EncodeError> {
+        if source_blocks.len() != self.k {
+            return Err(EncodeError::SourceCountChanged {
+                expected: self.k,
+                found: source_blocks.len(),
+            });
+        }
+        
+        self.original_lengths = source_blocks.iter().map(Vec::len).collect();
+        self.source_blocks = Arc::new(match &self.precode {
+            Some(precode) => precode.expand(&source_blocks)?,
+            None => source_blocks,
+        });
+        Ok(())
+    }
+    
+    /// Appends a source block after encoding has started, for producers
+    /// that do not know every block up front
+    ///
+    /// The degree distribution is rebuilt for the new block count, so blocks
+    /// generated from now on may reference the new index. Blocks generated
+    /// earlier were sampled for a smaller `k`: a decoder rederives a block's
+    /// dependencies from its seed and must be built with the final `k`, so
+    /// only blocks generated after the last push decode. Check `current_k`
+    /// before sending.
+    ///
+    /// The block must have the same length as the existing ones. Precoded
+    /// encoders cannot grow since their parity blocks depend on `k`. Shared
+    /// source blocks are copied before the first push.
+    pub fn push_source_block(&mut self, block: Vec<T>) -> Result<(), EncodeError> {
+        if self.precode.is_some() {
+            return Err(EncodeError::SourceCountChanged { expected: self.k, found: self.k + 1 });
+        }
+        if let Some(first) = self.source_blocks.first()
+            && first.len() != block.len()
+        {
+            return Err(EncodeError::MismatchedBlockLength { index: self.k, expected: first.len(), found: block.len() });
+        }
+        
+        self.original_lengths.push(block.len());
+        Arc::make_mut(&mut self.source_blocks).push(block);
+        self.k += 1;
+        self.prng.set_k(self.k);
+        if let Some(covered) = &mut self.covered {
+            covered.push(false);
+        }
+        Ok(())
+    }
+    
+    /// Returns the fingerprint of the encoder's `k`, distribution and
+    /// generator, to send along with the blocks
+    ///
+    /// It changes with `push_source_block`.
+    pub fn codec_id(&self) -> CodecId {
+        CodecId::of(&self.prng)
+    }
+    
+    /// Returns the number of source blocks the encoder currently draws from
+    ///
+    /// This is the `k` a decoder needs for blocks generated now; it changes
+    /// only through `push_source_block`.
+    pub fn current_k(&self) -> usize {
+        self.k
+    }
+    
+    /// Returns the length of the source blocks in symbols, that of the first
+    /// block, or 0 without any
+    ///
+    /// Blocks built by `new_padded` or `from_config` all have this length;
+    /// combining a block of another length fails with
+    /// `EncodeError::MismatchedBlockLength`.
+    pub fn block_size(&self) -> usize {
+        self.source_blocks.first().map_or(0, Vec::len)
+    }
+    
+    /// Returns the degree distribution, e.g. the `delta` and `c` of a robust
+    /// soliton distribution, to put in a transmission manifest
+    pub fn distribution(&self) -> DegreeDistribution {
+        self.prng.distribution()
+    }
+    
+    /// Returns the seed the next PRNG-drawn block will carry, or `None` if
+    /// the encoder was never seeded
+    ///
+    /// Blocks from the systematic prefix and coverage priming carry their
+    /// reserved `systematic_seed` instead and leave this unchanged.
+    pub fn current_seed(&self) -> Option<i64> {
+        self.prng.source().seed()
+    }
+    
+    /// Returns how many blocks the encoder has generated
+    ///
+    /// Every block from `generate_encoded_block` and the methods built on it
+    /// counts, including `generate_block_at`; `generate_repair_for` does
+    /// not. A fork starts from 0.
+    pub fn packets_generated(&self) -> usize {
+        self.packets_generated
     }
     
     /// Generates a single encoded block
@@ -42,50 +366,450 @@ impl Encoder {
     /// Returns a tuple containing:
     /// - The seed used for this block (for decoder)
     /// - The degree of the block
+    /// - The indices of the source blocks it combines
     /// - The encoded data block
-    pub fn generate_encoded_block(&mut self, seed: Option<i64>) -> (i64, usize, HashSet<usize>,Vec<i32>) {
+    ///
+    /// The degree always lies in `1..=n`, where `n` is the number of blocks
+    /// the encoder draws from (`k`, plus the parity blocks with a precode),
+    /// and equals the number of indices. Decoders reject anything outside
+    /// that range.
+    ///
+    /// Fails if the selected source blocks differ in length, if there are no
+    /// source blocks at all, or if `seed` is `None` and the encoder was never
+    /// seeded (see `reset_seed`).
+    pub fn generate_encoded_block(&mut self, seed: Option<i64>) -> Result<(i64, usize, BTreeSet<usize>, Vec<T>), EncodeError> {
+        let mut block = (0, 0, BTreeSet::new(), Vec::new());
+        self.generate_encoded_block_into(seed, &mut block)?;
+        Ok(block)
+    }
+    
+    /// Generates an encoded block like `generate_encoded_block`, writing it
+    /// into `block` instead of returning a new one
+    ///
+    /// The index set and data buffer of `block` are cleared and refilled, so
+    /// once they have grown to the largest degree and block length, passing
+    /// the same `block` again generates without allocating. Its contents are
+    /// unspecified after an error.
+    pub fn generate_encoded_block_into(&mut self, seed: Option<i64>, block: &mut GeneratedBlock<T>) -> Result<(), EncodeError> {
+        if self.source_blocks.is_empty() {
+            return Err(EncodeError::NoSourceBlocks);
+        }
+        
+        // Systematic prefix: source blocks verbatim, without touching the PRNG
+        if self.systematic && seed.is_none() && self.systematic_sent < self.k {
+            let index = self.systematic_sent;
+            self.systematic_sent += 1;
+            self.fill_verbatim(index, block);
+            self.packets_generated += 1;
+            return Ok(());
+        }
+        
+        if let Some(seed) = seed
+            && !self.prng.source().is_valid_seed(seed)
+        {
+            return Err(EncodeError::InvalidSeed { seed });
+        }
+        if seed.is_none() && self.prng.source().seed().is_none() {
+            return Err(EncodeError::Unseeded);
+        }
+        
         // Use the PRNG to get source block indices
-        let (blockseed, d, indices) = self.prng.get_src_blocks(seed);
+        let (blockseed, d, indices, data) = block;
+        (*blockseed, *d) = self.prng.get_src_blocks_into(seed, indices);
+        if seed.is_none()
+            && let Some(acknowledged) = &self.acknowledged
+        {
+            let useless = |indices: &BTreeSet<usize>| indices.iter().all(|&idx| acknowledged.get(idx) == Some(&true));
+            for _ in 1..MAX_FEEDBACK_DRAWS {
+                if !useless(indices) {
+                    break;
+                }
+                (*blockseed, *d) = self.prng.get_src_blocks_into(None, indices);
+            }
+        }
+        debug_assert!((1..=self.source_blocks.len()).contains(d) && indices.len() == *d);
         
-        // XOR the selected source blocks
-        let encoded_block = self.xor_blocks(&indices);
+        // Coverage priming: replace the sample with a degree-1 block for an
+        // index not covered yet
+        if seed.is_none()
+            && let Some(covered) = &mut self.covered
+            && let Some(uncovered) = covered.iter().position(|&c| !c)
+        {
+            let sampled = (*d == 1).then(|| *indices.iter().next().unwrap());
+            match sampled {
+                Some(index) if index < self.k && !covered[index] => covered[index] = true,
+                _ => {
+                    covered[uncovered] = true;
+                    self.fill_verbatim(uncovered, block);
+                    self.packets_generated += 1;
+                    return Ok(());
+                }
+            }
+        }
         
-        (blockseed, d, indices, encoded_block)
+        // XOR the selected source blocks
+        self.xor_blocks_into(indices, data)?;
+        self.packets_generated += 1;
+        Ok(())
     }
     
-    /// XORs the specified source blocks together
-    fn xor_blocks(&self, indices: &HashSet<usize>) -> Vec<i32> {
-        if indices.is_empty() {
-            return Vec::new();
+    /// Writes source block `index` into `block` as a degree-1 block under
+    /// its systematic seed
+    fn fill_verbatim(&self, index: usize, block: &mut GeneratedBlock<T>) {
+        let (blockseed, d, indices, data) = block;
+        (*blockseed, *d) = (systematic_seed(index), 1);
+        indices.clear();
+        indices.insert(index);
+        data.clear();
+        data.extend_from_slice(&self.source_blocks[index]);
+    }
+    
+    /// Generates the next `n` encoded blocks in one call
+    ///
+    /// The blocks are exactly what `n` calls to `generate_encoded_block(None)`
+    /// would return, in order. Fails like that method, returning no blocks.
+    pub fn generate_encoded_blocks(&mut self, n: usize) -> Result<Vec<GeneratedBlock<T>>, EncodeError> {
+        let mut blocks = Vec::with_capacity(n);
+        for _ in 0..n {
+            blocks.push(self.generate_encoded_block(None)?);
+        }
+        Ok(blocks)
+    }
+    
+    /// Generates block `index` of the encoder's `SeedSequence`
+    ///
+    /// The result depends only on the root seed and `index`, not on earlier
+    /// calls, so a retransmit request can name blocks by index. The running
+    /// state used by `generate_encoded_block(None)` is left untouched. Fails
+    /// with `SeedSequenceUnsupported` for generators other than MINSTD.
+    pub fn generate_block_at(&mut self, index: usize) -> Result<(i64, usize, BTreeSet<usize>, Vec<T>), EncodeError> {
+        self.check_seed_sequence()?;
+        let state = self.prng.source().seed();
+        let block = self.generate_encoded_block(Some(self.seeds.nth(index)));
+        if let Some(state) = state {
+            self.prng.set_seed(state);
+        }
+        block
+    }
+    
+    /// Generates repair blocks for source blocks a receiver reports missing
+    ///
+    /// Each distinct index gets one degree-1 block under its
+    /// `systematic_seed`, in ascending order, so the decoder recovers every
+    /// requested block no matter what else it holds. The PRNG is not
+    /// touched. Fails if an index is not below `source_block_count`.
+    pub fn generate_repair_for(&self, indices: &[usize]) -> Result<Vec<GeneratedBlock<T>>, EncodeError> {
+        let mut missing = indices.to_vec();
+        missing.sort_unstable();
+        missing.dedup();
+        if let Some(&index) = missing.iter().find(|&&index| index >= self.k) {
+            return Err(EncodeError::IndexOutOfRange { index, k: self.k });
+        }
+        Ok(missing
+            .into_iter()
+            .map(|index| (systematic_seed(index), 1, BTreeSet::from([index]), self.source_blocks[index].clone()))
+            .collect())
+    }
+    
+    /// Creates an encoder over the same source blocks whose running stream
+    /// starts at `SeedSequence::stream(stream_id)` of this encoder's root
+    ///
+    /// This encoder's own schedule after `reset_seed` runs through stream 0,
+    /// so forks should use ids from 1. With the default MINSTD generator,
+    /// encoders on different streams never send the same seed as long as
+    /// none of them makes more than 1,099,582 draws, about a hundred
+    /// thousand blocks at typical degrees, and a fork's first block is never
+    /// one `generate_block_at` would produce. Forks share the source blocks
+    /// without copying them, keep the precode and feedback, and keep the
+    /// `SeedSequence`, so any fork can answer `generate_block_at`. They leave
+    /// out the systematic prefix and coverage priming, whose reserved seeds
+    /// every fork would repeat. Fails if the encoder was never seeded,
+    /// `stream_id` is not below `STREAM_COUNT` or the generator is not
+    /// MINSTD.
+    pub fn fork(&self, stream_id: u16) -> Result<Self, EncodeError> {
+        if self.prng.source().seed().is_none() {
+            return Err(EncodeError::Unseeded);
+        }
+        self.check_seed_sequence()?;
+        let start = self.seeds.stream(stream_id).ok_or(EncodeError::StreamOutOfRange { stream_id })?;
+        let mut prng = PRNG::with_table_and_source(self.prng.table().clone(), self.prng.source().clone_box());
+        prng.set_seed(start);
+        Ok(Self {
+            source_blocks: self.source_blocks.clone(),
+            prng,
+            k: self.k,
+            original_lengths: self.original_lengths.clone(),
+            systematic: false,
+            systematic_sent: 0,
+            precode: self.precode.clone(),
+            covered: None,
+            acknowledged: self.acknowledged.clone(),
+            seeds: self.seeds,
+            packets_generated: 0,
+        })
+    }
+    
+    /// Returns the sequence `generate_block_at` derives seeds from
+    pub fn seed_sequence(&self) -> SeedSequence {
+        self.seeds
+    }
+    
+    /// Fails unless the generator accepts the `SeedSequence`'s seeds
+    fn check_seed_sequence(&self) -> Result<(), EncodeError> {
+        let source = self.prng.source();
+        if source.supports_seed_sequence() {
+            Ok(())
+        } else {
+            Err(EncodeError::SeedSequenceUnsupported { source: source.name() })
+        }
+    }
+    
+    /// Returns an endless iterator of encoded blocks
+    ///
+    /// Each item is what `generate_encoded_block(None)` would return, so the
+    /// PRNG keeps advancing from its current state. The iterator ends early
+    /// only if a block cannot be encoded because source lengths differ.
+    pub fn iter(&mut self) -> EncodedBlocks<'_, T> {
+        EncodedBlocks { encoder: self }
+    }
+    
+    /// XORs the source blocks at `indices` without touching the PRNG
+    ///
+    /// This builds blocks for a hand-picked dependency set, e.g. for decoder
+    /// tests or alternative degree schedules. The indices cover the blocks
+    /// the encoder draws from, precode parity blocks included. Fails if an
+    /// index is out of range or the selected blocks differ in length.
+    pub fn encode_indices(&self, indices: &BTreeSet<usize>) -> Result<Vec<T>, EncodeError> {
+        let n = self.source_blocks.len();
+        if let Some(&index) = indices.iter().find(|&&index| index >= n) {
+            return Err(EncodeError::IndexOutOfRange { index, k: n });
         }
+        self.xor_blocks(indices)
+    }
+    
+    /// XORs the specified source blocks together
+    fn xor_blocks(&self, indices: &BTreeSet<usize>) -> Result<Vec<T>, EncodeError> {
+        let mut result = Vec::new();
+        self.xor_blocks_into(indices, &mut result)?;
+        Ok(result)
+    }
+    
+    /// XORs the specified source blocks together into `result`, replacing
+    /// its contents
+    fn xor_blocks_into(&self, indices: &BTreeSet<usize>, result: &mut Vec<T>) -> Result<(), EncodeError> {
+        result.clear();
         
         // Get the first block as the starting point
-        let first_idx = *indices.iter().next().unwrap();
-        let mut result = self.source_blocks[first_idx].clone();
-        
-        // XOR with the remaining blocks
-        for &idx in indices.iter().skip(1) {
-            if !self.source_blocks[idx].is_empty() {
-                // Ensure blocks are the same length
-                if result.len() != self.source_blocks[idx].len() {
-                    println!("Source block {} length {} does not match first block length {}", idx, self.source_blocks[idx].len(), result.len());
-                    panic!("Source blocks must be of the same length");
-                }
-                
-                // XOR operation directly on i32 values
-                for i in 0..result.len() {
-                    result[i] ^= self.source_blocks[idx][i];
-                }
+        let mut ordered = indices.iter().copied();
+        let Some(first_idx) = ordered.next() else {
+            return Ok(());
+        };
+        result.extend_from_slice(&self.source_blocks[first_idx]);
+        
+        // Check the remaining blocks are the same length
+        for idx in ordered.clone() {
+            if !self.source_blocks[idx].is_empty() && result.len() != self.source_blocks[idx].len() {
+                lt_warn!(
+                    "source block {} has length {} but {} was expected",
+                    idx, self.source_blocks[idx].len(), result.len()
+                );
+                return Err(EncodeError::MismatchedBlockLength {
+                    index: idx,
+                    expected: result.len(),
+                    found: self.source_blocks[idx].len(),
+                });
             }
         }
         
-        result
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        {
+            let others: Vec<&[T]> = ordered.map(|idx| self.source_blocks[idx].as_slice()).collect();
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            xor_parallel(result, &others, threads);
+        }
+        #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+        for idx in ordered {
+            xor_into(result, &self.source_blocks[idx]);
+        }
+        
+        Ok(())
     }
     
     /// Get the number of source blocks
     pub fn source_block_count(&self) -> usize {
         self.k
     }
+    
+    /// Get the length of each source block before any padding was applied
+    pub fn original_lengths(&self) -> &[usize] {
+        &self.original_lengths
+    }
+    
+    /// Get the total unpadded length of the source blocks in symbols
+    ///
+    /// For data cut into equal blocks with only the last one padded, as by
+    /// `new_padded`, this is the original data length to transmit for
+    /// `Decoder::set_data_len`.
+    pub fn data_len(&self) -> usize {
+        self.original_lengths.iter().sum()
+    }
+    
+    /// Get the unpadded length of the final source block, for
+    /// `Decoder::new_with_last_block_size`
+    pub fn last_block_size(&self) -> Option<usize> {
+        self.original_lengths.last().copied()
+    }
+}
+
+impl Encoder {
+    /// Creates a new Encoder with the given source blocks
+    ///
+    /// Fails if the RSD parameters are invalid for the block count, see
+    /// `validate_params`. `new_generic` takes blocks of any `XorElement`.
+    pub fn new(source_blocks: Vec<Vec<i32>>, delta: f64, c: f64) -> Result<Self, DistError> {
+        Self::new_generic(source_blocks, delta, c)
+    }
+    
+    /// `with_distribution_generic` for `i32` symbols
+    pub fn with_distribution(source_blocks: Vec<Vec<i32>>, distribution: DegreeDistribution) -> Result<Self, DistError> {
+        Self::with_distribution_generic(source_blocks, distribution)
+    }
+    
+    /// `with_source_generic` for `i32` symbols
+    pub fn with_source(source_blocks: Vec<Vec<i32>>, distribution: DegreeDistribution, source: Box<dyn RandomSource>) -> Result<Self, DistError> {
+        Self::with_source_generic(source_blocks, distribution, source)
+    }
+    
+    /// `with_table_generic` for `i32` symbols
+    pub fn with_table(source_blocks: Vec<Vec<i32>>, table: Arc<DistributionTable>) -> Result<Self, EncodeError> {
+        Self::with_table_generic(source_blocks, table)
+    }
+    
+    /// `new_default_generic` for `i32` symbols
+    pub fn new_default(source_blocks: Vec<Vec<i32>>, seed: Option<i64>) -> Self {
+        Self::new_default_generic(source_blocks, seed)
+    }
+    
+    /// `new_padded_generic` for `i32` symbols
+    pub fn new_padded(source_blocks: Vec<Vec<i32>>, seed: Option<i64>) -> Self {
+        Self::new_padded_generic(source_blocks, seed)
+    }
+    
+    /// `new_systematic_generic` for `i32` symbols
+    pub fn new_systematic(source_blocks: Vec<Vec<i32>>, seed: Option<i64>) -> Self {
+        Self::new_systematic_generic(source_blocks, seed)
+    }
+    
+    /// `with_precode_generic` for `i32` symbols
+    pub fn with_precode(source_blocks: Vec<Vec<i32>>, redundancy: f64, seed: Option<i64>) -> Result<Self, EncodeError> {
+        Self::with_precode_generic(source_blocks, redundancy, seed)
+    }
+    
+    /// Returns the SHA-256 digest of the source blocks, for the receiver to
+    /// pass to `Decoder::set_source_digest`
+    ///
+    /// Precode parity blocks are not included.
+    pub fn source_digest(&self) -> [u8; 32] {
+        sha256::source_digest(&self.source_blocks[..self.k])
+    }
+}
+
+/// XORs every block in `others` into `result`, one block at a time
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn xor_serial<T: XorElement>(result: &mut [T], others: &[&[T]]) {
+    for block in others {
+        xor_into(result, block);
+    }
+}
+
+// Seeds `generate_encoded_block` draws per block at most while skipping
+// blocks the receiver's feedback makes useless
+const MAX_FEEDBACK_DRAWS: usize = 64;
+
+// Blocks shorter than this many symbols are not worth spawning threads for
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const PARALLEL_MIN_LEN: usize = 1 << 16;
+
+/// XORs every block in `others` into `result`, splitting the data positions
+/// across `threads` threads
+///
+/// Each thread owns a disjoint range of `result` and XORs that range of
+/// every block into it, so the output matches `xor_serial` exactly, also for
+/// blocks shorter than `result`.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+fn xor_parallel<T: XorElement>(result: &mut [T], others: &[&[T]], threads: usize) {
+    if threads == 1 || result.len() < PARALLEL_MIN_LEN || others.is_empty() {
+        xor_serial(result, others);
+        return;
+    }
+    
+    let chunk_len = result.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        for (i, chunk) in result.chunks_mut(chunk_len).enumerate() {
+            let offset = i * chunk_len;
+            scope.spawn(move || {
+                for block in others {
+                    xor_into(chunk, block.get(offset..).unwrap_or(&[]));
+                }
+            });
+        }
+    });
+}
+
+/// Iterator over the encoded blocks of an `Encoder`, see `Encoder::iter`
+pub struct EncodedBlocks<'a, T: XorElement = i32> {
+    encoder: &'a mut Encoder<T>,
+}
+
+impl<T: XorElement> Iterator for EncodedBlocks<'_, T> {
+    type Item = GeneratedBlock<T>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.encoder.generate_encoded_block(None).ok()
+    }
+}
+
+impl<'a, T: XorElement> IntoIterator for &'a mut Encoder<T> {
+    type Item = GeneratedBlock<T>;
+    type IntoIter = EncodedBlocks<'a, T>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Owning iterator over the encoded blocks of an `Encoder`, from its
+/// `IntoIterator` impl
+///
+/// Yields the same blocks as `Encoder::iter`, but can be moved to another
+/// thread or stored, e.g. to feed a channel. `into_inner` gives the encoder
+/// back.
+pub struct IntoEncodedBlocks<T: XorElement = i32> {
+    encoder: Encoder<T>,
+}
+
+impl<T: XorElement> IntoEncodedBlocks<T> {
+    /// Returns the encoder, positioned after the last block yielded
+    pub fn into_inner(self) -> Encoder<T> {
+        self.encoder
+    }
+}
+
+impl<T: XorElement> Iterator for IntoEncodedBlocks<T> {
+    type Item = GeneratedBlock<T>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.encoder.generate_encoded_block(None).ok()
+    }
+}
+
+impl<T: XorElement> IntoIterator for Encoder<T> {
+    type Item = GeneratedBlock<T>;
+    type IntoIter = IntoEncodedBlocks<T>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        IntoEncodedBlocks { encoder: self }
+    }
 }
 
 #[cfg(test)]
@@ -112,7 +836,7 @@ mod tests {
         
         // Generate a block with a known seed for reproducibility
         for i in 2412..2430 {
-            let (seed, d, indices, encoded_block) = encoder.generate_encoded_block(None);
+            let (seed, d, indices, encoded_block) = encoder.generate_encoded_block(None).unwrap();
             // println!("encoded_block = {}", encoded_block);
             println!("indices = {:?}", indices);
             println!("d = {}", d);
@@ -122,11 +846,626 @@ mod tests {
         }
         // assert_eq!(encoded_block.len(), 3);
         println!("After Iteration, Single call");
-        let (seed, d, indices, encoded_block) = encoder.generate_encoded_block(None);
+        let (seed, d, indices, encoded_block) = encoder.generate_encoded_block(None).unwrap();
         println!("indices = {:?}", indices);
         println!("d = {}", d);
         println!("seed = {}", seed); 
     }
+    
+    #[test]
+    fn test_precode_lowers_overhead() {
+        use crate::luby_transform::decoder::Decoder;
+        
+        let k = 40;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * 31]).collect();
+        
+        // Count blocks needed to decode, summed over a fixed set of seeds
+        let blocks_needed = |precode: bool| -> usize {
+            (1..=20i64)
+                .map(|seed| {
+                    let seed = Some(seed * 7919);
+                    let (mut encoder, mut decoder) = if precode {
+                        (
+                            Encoder::with_precode(source_blocks.clone(), 0.1, seed).unwrap(),
+                            Decoder::with_precode(k, 2, 0.1).unwrap(),
+                        )
+                    } else {
+                        (Encoder::new_default(source_blocks.clone(), seed), Decoder::new_default(k, 2))
+                    };
+                    let mut used = 0;
+                    while !decoder.is_complete() {
+                        let (seed, d, _, block) = encoder.generate_encoded_block(None).unwrap();
+                        decoder.add_encoded_block(seed, d, block);
+                        used += 1;
+                    }
+                    assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+                    used
+                })
+                .sum()
+        };
+        
+        let plain = blocks_needed(false);
+        let precoded = blocks_needed(true);
+        assert!(precoded < plain);
+    }
+    
+    #[test]
+    fn test_systematic_prefix() {
+        use crate::luby_transform::decoder::Decoder;
+        
+        let k = 16;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i + 100]).collect();
+        let mut encoder = Encoder::new_systematic(source_blocks.clone(), Some(5));
+        let mut decoder = Decoder::new_default(k, 2);
+        
+        for i in 0..k {
+            assert!(!decoder.is_complete());
+            let (seed, d, indices, block) = encoder.generate_encoded_block(None).unwrap();
+            assert_eq!((d, indices), (1, BTreeSet::from([i])));
+            decoder.add_encoded_block(seed, d, block);
+        }
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+        
+        // After the prefix the encoder produces regular LT blocks
+        let (seed, _, _, _) = encoder.generate_encoded_block(None).unwrap();
+        assert_eq!(seed, 5);
+        
+        // Any encoder can switch the prefix on, which restarts it
+        let mut encoder = Encoder::new_default(source_blocks, Some(5));
+        assert!(!encoder.is_systematic());
+        encoder.generate_encoded_block(None).unwrap();
+        encoder.set_systematic(true);
+        let prefix: Vec<i64> = encoder.iter().take(k + 1).map(|(seed, _, _, _)| seed).collect();
+        assert_eq!(prefix[..k], (0..k).map(systematic_seed).collect::<Vec<_>>());
+        assert!(prefix[k] > 0);
+        encoder.set_systematic(false);
+        assert!(encoder.generate_encoded_block(None).unwrap().0 > 0);
+    }
+    
+    #[test]
+    fn test_reset_seed_and_source_swap() {
+        let mut encoder = Encoder::new_default(vec![vec![1], vec![2], vec![3]], Some(8));
+        let cdf = encoder.prng.cdf().as_ptr();
+        let first: Vec<_> = (0..5).map(|_| encoder.generate_encoded_block(None).unwrap()).collect();
+        
+        encoder.reset_seed(8);
+        let again: Vec<_> = (0..5).map(|_| encoder.generate_encoded_block(None).unwrap()).collect();
+        assert_eq!(first, again);
+        assert_eq!(encoder.prng.cdf().as_ptr(), cdf);
+        
+        assert_eq!(
+            encoder.set_source_blocks(vec![vec![4]]),
+            Err(EncodeError::SourceCountChanged { expected: 3, found: 1 })
+        );
+        encoder.set_source_blocks(vec![vec![4], vec![5], vec![6]]).unwrap();
+        encoder.reset_seed(8);
+        for (seed, d, indices, _) in first {
+            let (_, _, _, block) = encoder.generate_encoded_block(Some(seed)).unwrap();
+            let expected = indices.iter().fold(0, |acc, &i| acc ^ (i as i32 + 4));
+            assert_eq!((d, block), (indices.len(), vec![expected]));
+        }
+        assert_eq!(encoder.prng.cdf().as_ptr(), cdf);
+    }
+    
+    #[test]
+    fn test_mismatched_lengths() {
+        let source_blocks = vec![vec![1, 2, 3], vec![4, 5]];
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(1));
+        
+        // Some block combines both sources and must report the mismatch
+        let err = (0..100)
+            .find_map(|_| encoder.generate_encoded_block(None).err())
+            .expect("expected a mismatched length error");
+        assert!(matches!(err, EncodeError::MismatchedBlockLength { .. }));
+        
+        let mut padded = Encoder::new_padded(source_blocks, Some(1));
+        assert_eq!(padded.original_lengths(), &[3, 2]);
+        for _ in 0..100 {
+            let (_, _, _, block) = padded.generate_encoded_block(None).unwrap();
+            assert_eq!(block.len(), 3);
+        }
+    }
+    
+    #[cfg(feature = "logging")]
+    #[test]
+    fn test_mismatched_lengths_logged() {
+        use std::sync::Mutex;
+        
+        struct Capture(Mutex<Vec<(log::Level, String)>>);
+        
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+            }
+            
+            fn flush(&self) {}
+        }
+        
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+        
+        let mut encoder = Encoder::new_default(vec![vec![1, 2, 3], vec![4, 5]], Some(1));
+        assert!((0..100).any(|_| encoder.generate_encoded_block(None).is_err()));
+        
+        let records = CAPTURE.0.lock().unwrap();
+        assert!(records
+            .iter()
+            .any(|(level, message)| *level == log::Level::Warn && message.starts_with("source block")));
+    }
+    
+    #[test]
+    fn test_degree_one_coverage() {
+        use crate::luby_transform::decoder::Decoder;
+        
+        let k = 4;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i + 1, i * 9]).collect();
+        for seed in 1..=20 {
+            let mut encoder = Encoder::new_default(source_blocks.clone(), Some(seed));
+            encoder.set_degree_one_coverage(true);
+            let mut decoder = Decoder::new_default(k, 2);
+            
+            for (seed, d, _, data) in encoder.iter().take(k) {
+                assert_eq!(d, 1);
+                decoder.add_encoded_block(seed, d, data);
+            }
+            assert!(decoder.is_complete());
+            assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+        }
+    }
+    
+    #[test]
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn test_parallel_xor_matches_serial() {
+        let len = PARALLEL_MIN_LEN * 3 + 17;
+        let blocks: Vec<Vec<i32>> = (0..6)
+            .map(|b| (0..len as i32).map(|i| i.wrapping_mul(0x9e37_79b9u32 as i32) ^ b).collect())
+            .collect();
+        let mut others: Vec<&[i32]> = blocks[1..].iter().map(Vec::as_slice).collect();
+        
+        // Empty and short blocks only touch the positions they cover
+        others.push(&[]);
+        others.push(&blocks[2][..PARALLEL_MIN_LEN + 5]);
+        
+        let mut serial = blocks[0].clone();
+        xor_serial(&mut serial, &others);
+        for threads in [1, 2, 4] {
+            let mut parallel = blocks[0].clone();
+            xor_parallel(&mut parallel, &others, threads);
+            assert_eq!(parallel, serial, "{} threads", threads);
+        }
+    }
+    
+    #[test]
+    fn test_iter_advances_prng() {
+        let source_blocks: Vec<Vec<i32>> = (0..10).map(|i| vec![i, i + 1]).collect();
+        let mut encoder = Encoder::new_default(source_blocks, Some(1));
+        
+        // Number of LCG steps from `from` to `to`, if reachable at all
+        let steps = |from: i64, to: i64| {
+            let mut state = from;
+            (1..10_000).find(|_| {
+                state = state * 16807 % ((1 << 31) - 1);
+                state == to
+            })
+        };
+        
+        let seeds: Vec<i64> = encoder.iter().take(50).map(|(seed, ..)| seed).collect();
+        assert_eq!(seeds.len(), 50);
+        assert_eq!(seeds[0], 1);
+        for pair in seeds.windows(2) {
+            assert!(steps(pair[0], pair[1]).is_some(), "seeds = {:?}", pair);
+        }
+        
+        // A fresh iterator continues where the previous one stopped
+        let (next_seed, ..) = encoder.iter().next().unwrap();
+        assert!(steps(seeds[49], next_seed).is_some());
+    }
+    
+    #[test]
+    fn test_single_and_empty_source() {
+        use crate::luby_transform::decoder::Decoder;
+        use crate::luby_transform::error::LtError;
+        use crate::luby_transform::prng::{DEFAULT_C, DEFAULT_DELTA};
+        
+        // A single block is always sent on its own and decodes immediately
+        let mut encoder = Encoder::new(vec![vec![5, -6, 7]], DEFAULT_DELTA, DEFAULT_C).unwrap();
+        assert_eq!(encoder.generate_encoded_block(None), Err(EncodeError::Unseeded));
+        encoder.reset_seed(3);
+        let mut decoder = Decoder::new(1, 3, DEFAULT_DELTA, DEFAULT_C).unwrap();
+        let (seed, d, indices, data) = encoder.generate_encoded_block(None).unwrap();
+        assert_eq!((d, indices), (1, BTreeSet::from([0])));
+        decoder.add_encoded_block(seed, d, data);
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(vec![vec![5, -6, 7]]));
+        
+        // Later blocks still get fresh seeds
+        let seeds: BTreeSet<i64> = encoder.iter().take(20).map(|(seed, ..)| seed).collect();
+        assert_eq!(seeds.len(), 20);
+        
+        // No source blocks is an error rather than a NaN distribution
+        assert_eq!(Encoder::new(Vec::new(), DEFAULT_DELTA, DEFAULT_C).err(), Some(DistError::NoSourceBlocks));
+        assert_eq!(Encoder::with_distribution(Vec::new(), DegreeDistribution::Ideal).err(), Some(DistError::NoSourceBlocks));
+        assert_eq!(Encoder::with_precode(Vec::new(), 0.1, None).err(), Some(EncodeError::NoSourceBlocks));
+        assert_eq!(
+            Encoder::with_precode(vec![vec![1]], f64::INFINITY, Some(1)).err(),
+            Some(EncodeError::InvalidRedundancy(f64::INFINITY))
+        );
+        assert_eq!(Decoder::new(0, 3, DEFAULT_DELTA, DEFAULT_C).err(), Some(DistError::NoSourceBlocks));
+        assert_eq!(Encoder::new_default(Vec::new(), Some(1)).generate_encoded_block(None), Err(EncodeError::NoSourceBlocks));
+        
+        let mut decoder = Decoder::new_default(0, 3);
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.add_encoded_block(1, 1, vec![0; 3]), None);
+        
+        // Both kinds of failure propagate as `LtError`
+        let first_block = |source_blocks: Vec<Vec<i32>>| -> Result<Vec<i32>, LtError> {
+            let mut encoder = Encoder::new(source_blocks, DEFAULT_DELTA, DEFAULT_C)?;
+            Ok(encoder.generate_encoded_block(Some(1))?.3)
+        };
+        assert_eq!(first_block(Vec::new()), Err(LtError::Dist(DistError::NoSourceBlocks)));
+        assert_eq!(first_block(vec![vec![4]]), Ok(vec![4]));
+    }
+    
+    #[test]
+    fn test_shared_source() {
+        let source_blocks: Arc<Vec<Vec<i32>>> = Arc::new((0..20).map(|i| vec![i, i * 17]).collect());
+        let mut owned = Encoder::new_default(source_blocks.to_vec(), Some(1));
+        let expected: Vec<_> = (0..10).map(|i| owned.generate_block_at(i).unwrap()).collect();
+        
+        // Each thread encodes from the same copy
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let mut encoder = Encoder::with_shared_source(Arc::clone(&source_blocks), Some(1));
+                std::thread::spawn(move || (0..10).map(|i| encoder.generate_block_at(i).unwrap()).collect::<Vec<_>>())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+        
+        // Growing an encoder detaches it from the shared blocks
+        let mut encoder = Encoder::with_shared_source(Arc::clone(&source_blocks), Some(1));
+        encoder.push_source_block(vec![7, 7]).unwrap();
+        assert_eq!(source_blocks.len(), 20);
+        assert_eq!(Arc::strong_count(&source_blocks), 1);
+    }
+    
+    #[test]
+    fn test_generic_elements() {
+        use crate::luby_transform::decoder::Decoder;
+        
+        fn round_trip<T: XorElement + PartialEq + core::fmt::Debug>(source_blocks: Vec<Vec<T>>) {
+            let k = source_blocks.len();
+            let block_size = source_blocks[0].len();
+            let mut encoder = Encoder::new_default_generic(source_blocks.clone(), Some(21));
+            let mut decoder = Decoder::new_default_generic(k, block_size);
+            for (seed, d, _, data) in encoder.iter().take(10 * k) {
+                decoder.add_encoded_block(seed, d, data);
+                if decoder.is_complete() {
+                    break;
+                }
+            }
+            assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        }
+        
+        round_trip::<u8>((0..40u8).map(|i| vec![i, i.wrapping_mul(7), !i]).collect());
+        round_trip::<u64>((0..40u64).map(|i| vec![i << 40 | i, u64::MAX - i]).collect());
+        round_trip::<[u8; 4]>((0..40u8).map(|i| vec![[i, !i, 3, i ^ 0x5a]; 2]).collect());
+    }
+    
+    #[test]
+    fn test_push_source_block() {
+        use crate::luby_transform::decoder::Decoder;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..16).map(|i| vec![i, -i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks[..8].to_vec(), Some(6));
+        
+        // Blocks generated so far only reference the first eight sources
+        for (_, _, indices, _) in encoder.iter().take(20) {
+            assert!(indices.iter().all(|&idx| idx < 8));
+        }
+        
+        for block in &source_blocks[8..] {
+            encoder.push_source_block(block.clone()).unwrap();
+        }
+        assert_eq!(encoder.current_k(), 16);
+        assert_eq!(
+            encoder.push_source_block(vec![1]),
+            Err(EncodeError::MismatchedBlockLength { index: 16, expected: 2, found: 1 })
+        );
+        
+        // Once k is final, later blocks decode with a decoder built for it
+        let mut decoder = Decoder::new_default(16, 2);
+        let mut referenced_new = false;
+        for (seed, d, indices, data) in encoder.iter().take(200) {
+            referenced_new |= indices.iter().any(|&idx| idx >= 8);
+            decoder.add_encoded_block(seed, d, data);
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert!(referenced_new);
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        
+        let mut precoded = Encoder::with_precode(vec![vec![1], vec![2]], 0.5, None).unwrap();
+        assert!(precoded.push_source_block(vec![3]).is_err());
+    }
+    
+    #[test]
+    fn test_encode_indices() {
+        use crate::luby_transform::gaussian;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..8).map(|i| vec![i * 13, 100 - i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(5));
+        let before = encoder.generate_encoded_block(None).unwrap();
+        encoder.reset_seed(5);
+        
+        let pair = encoder.encode_indices(&BTreeSet::from([2, 6])).unwrap();
+        let single = encoder.encode_indices(&BTreeSet::from([6])).unwrap();
+        assert_eq!(single, source_blocks[6]);
+        
+        // The pair and the degree-1 block determine both sources
+        let solution = gaussian::solve(&[2, 6], vec![(BTreeSet::from([2, 6]), pair), (BTreeSet::from([6]), single)]);
+        assert_eq!(solution.solved, vec![(2, source_blocks[2].clone()), (6, source_blocks[6].clone())]);
+        
+        assert_eq!(
+            encoder.encode_indices(&BTreeSet::from([1, 8])),
+            Err(EncodeError::IndexOutOfRange { index: 8, k: 8 })
+        );
+        
+        // The PRNG was not advanced
+        assert_eq!(encoder.generate_encoded_block(None).unwrap(), before);
+    }
+    
+    #[test]
+    fn test_generate_block_at() {
+        let source_blocks: Vec<Vec<i32>> = (0..30).map(|i| vec![i, i * 11]).collect();
+        let mut first = Encoder::new_default(source_blocks.clone(), Some(77));
+        let mut second = Encoder::new_default(source_blocks, Some(77));
+        
+        // Unrelated calls on one encoder do not change block 7
+        first.iter().take(5).for_each(drop);
+        let block = first.generate_block_at(7).unwrap();
+        assert_eq!(second.generate_block_at(7).unwrap(), block);
+        assert_eq!(block.0, SeedSequence::new(77).nth(7));
+        
+        // The running stream is left where it was
+        let mut fresh = Encoder::new_default((0..30).map(|i| vec![i, i * 11]).collect(), Some(77));
+        assert_eq!(second.generate_encoded_block(None).unwrap(), fresh.generate_encoded_block(None).unwrap());
+        
+        let seeds: BTreeSet<i64> = (0..1000).map(|i| first.seed_sequence().nth(i)).collect();
+        assert_eq!(seeds.len(), 1000);
+        assert!(seeds.iter().all(|&seed| seed > 0));
+    }
+    
+    #[test]
+    fn test_generate_encoded_block_into() {
+        let source_blocks: Vec<Vec<i32>> = (0..25).map(|i| vec![i; 64]).collect();
+        let mut reference = Encoder::new_default(source_blocks.clone(), Some(31));
+        let mut encoder = Encoder::new_default(source_blocks, Some(31));
+        encoder.set_degree_one_coverage(true);
+        reference.set_degree_one_coverage(true);
+        
+        // The same blocks as `generate_encoded_block`, in the same buffers
+        let mut block = (0, 0, BTreeSet::new(), Vec::with_capacity(64));
+        let data_ptr = block.3.as_ptr();
+        for _ in 0..100 {
+            encoder.generate_encoded_block_into(None, &mut block).unwrap();
+            assert_eq!(block, reference.generate_encoded_block(None).unwrap());
+            assert_eq!(block.3.as_ptr(), data_ptr);
+        }
+        encoder.generate_encoded_block_into(Some(5), &mut block).unwrap();
+        assert_eq!(block, reference.generate_encoded_block(Some(5)).unwrap());
+    }
+    
+    #[test]
+    fn test_seed_guard() {
+        let source_blocks: Vec<Vec<i32>> = (0..10).map(|i| vec![i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), None);
+        assert_eq!(encoder.current_seed(), Some(DEFAULT_SEED));
+        let blocks: BTreeSet<i64> = encoder.iter().take(20).map(|(seed, _, _, _)| seed).collect();
+        assert_eq!(blocks.len(), 20);
+        
+        // Seed 0 is remapped rather than sticking at the LCG's fixed point
+        let mut zero = Encoder::new_default(source_blocks, Some(0));
+        assert_eq!(zero.generate_encoded_block(None).unwrap().0, DEFAULT_SEED);
+        
+        // Explicit seeds must be states the generator can produce
+        assert_eq!(zero.generate_encoded_block(Some(0)), Err(EncodeError::InvalidSeed { seed: 0 }));
+        assert_eq!(zero.generate_encoded_block(Some(-4)), Err(EncodeError::InvalidSeed { seed: -4 }));
+        assert_eq!(zero.packets_generated(), 1);
+    }
+    
+    #[test]
+    fn test_accessors() {
+        use crate::luby_transform::prng::DEFAULT_C;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..12).map(|i| vec![i; 5]).collect();
+        let distribution = DegreeDistribution::Robust { delta: 0.1, c: DEFAULT_C };
+        let mut encoder = Encoder::with_distribution(source_blocks, distribution).unwrap();
+        assert_eq!(encoder.block_size(), 5);
+        assert_eq!(encoder.distribution(), distribution);
+        assert_eq!(encoder.current_seed(), None);
+        
+        encoder.reset_seed(60);
+        assert_eq!(encoder.current_seed(), Some(60));
+        let (seed, _, _, _) = encoder.generate_encoded_block(None).unwrap();
+        assert_eq!(seed, 60);
+        let next = encoder.current_seed().unwrap();
+        assert_eq!(encoder.generate_encoded_block(None).unwrap().0, next);
+        encoder.generate_block_at(3).unwrap();
+        assert_eq!(encoder.packets_generated(), 3);
+        
+        // Failed and repair blocks are not counted
+        encoder.generate_repair_for(&[0, 1]).unwrap();
+        encoder.push_source_block(vec![1; 2]).unwrap_err();
+        assert_eq!(encoder.packets_generated(), 3);
+        assert_eq!(encoder.fork(1).unwrap().packets_generated(), 0);
+        assert_eq!(Encoder::new_default(Vec::new(), None).block_size(), 0);
+    }
+    
+    #[test]
+    fn test_fork() {
+        use crate::luby_transform::decoder::Decoder;
+        use crate::luby_transform::prng::{LcgParams, Minstd, DEFAULT_C, DEFAULT_DELTA};
+        
+        let k = 40;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 3, i ^ 0x55]).collect();
+        let mut encoder = Encoder::new_systematic(source_blocks.clone(), Some(123));
+        let mut forks: Vec<Encoder> = (1..=3).map(|id| encoder.fork(id).unwrap()).collect();
+        
+        // No seed repeats across the parent and its forks
+        let mut seeds = BTreeSet::new();
+        let mut decoder: Decoder = Decoder::new_default(k, 2);
+        for (seed, d, _, data) in encoder.iter().take(500) {
+            assert!(seeds.insert(seed));
+            decoder.add_encoded_block(seed, d, data);
+        }
+        for fork in &mut forks {
+            for (seed, d, _, data) in fork.iter().take(500) {
+                assert!(seed > 0);
+                assert!(seeds.insert(seed));
+                decoder.add_encoded_block(seed, d, data);
+            }
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks.clone()));
+        
+        // Forks are deterministic and agree on indexed blocks
+        let mut again = encoder.fork(2).unwrap();
+        let mut fresh = Encoder::new_default(source_blocks.clone(), Some(123)).fork(2).unwrap();
+        assert_eq!(again.generate_encoded_block(None).unwrap(), fresh.generate_encoded_block(None).unwrap());
+        assert_eq!(again.generate_block_at(9).unwrap(), encoder.generate_block_at(9).unwrap());
+        
+        let last = crate::luby_transform::prng::STREAM_COUNT;
+        assert!(encoder.fork(last - 1).is_ok());
+        assert_eq!(encoder.fork(last).err(), Some(EncodeError::StreamOutOfRange { stream_id: last }));
+        
+        let unseeded = Encoder::new(source_blocks.clone(), DEFAULT_DELTA, DEFAULT_C).unwrap();
+        assert_eq!(unseeded.fork(1).err(), Some(EncodeError::Unseeded));
+        
+        // Other LCG constants have a different state space
+        let lcg = Minstd::with_params(LcgParams { a: 3, m: 101 }).unwrap();
+        let mut other = Encoder::with_source(source_blocks, DegreeDistribution::default(), Box::new(lcg)).unwrap();
+        other.reset_seed(5);
+        let unsupported = EncodeError::SeedSequenceUnsupported { source: "lcg" };
+        assert_eq!(other.generate_block_at(3).err(), Some(unsupported.clone()));
+        assert_eq!(other.fork(1).err(), Some(unsupported));
+    }
+    
+    #[test]
+    fn test_generate_encoded_blocks() {
+        use crate::luby_transform::prng::{DEFAULT_C, DEFAULT_DELTA};
+        
+        let source_blocks: Vec<Vec<i32>> = (0..16).map(|i| vec![i, i << 4]).collect();
+        let mut batched = Encoder::new_default(source_blocks.clone(), Some(6));
+        let mut single = Encoder::new_default(source_blocks, Some(6));
+        
+        let blocks = batched.generate_encoded_blocks(40).unwrap();
+        assert_eq!(blocks.len(), 40);
+        for block in blocks {
+            assert_eq!(single.generate_encoded_block(None).unwrap(), block);
+        }
+        assert_eq!(batched.generate_encoded_block(None), single.generate_encoded_block(None));
+        
+        let mut unseeded = Encoder::new(vec![vec![1]], DEFAULT_DELTA, DEFAULT_C).unwrap();
+        assert_eq!(unseeded.generate_encoded_blocks(3), Err(EncodeError::Unseeded));
+    }
+    
+    #[test]
+    fn test_into_iterator() {
+        use std::sync::mpsc;
+        
+        let source_blocks: Vec<Vec<i32>> = (0..10).map(|i| vec![i * 2]).collect();
+        let mut reference = Encoder::new_default(source_blocks.clone(), Some(3));
+        let expected: Vec<GeneratedBlock> = reference.iter().take(12).collect();
+        
+        // The owned stream can move to a producer thread
+        let encoder = Encoder::new_default(source_blocks.clone(), Some(3));
+        let (tx, rx) = mpsc::channel();
+        let producer = std::thread::spawn(move || {
+            let mut blocks = encoder.into_iter();
+            for block in blocks.by_ref().take(12) {
+                tx.send(block).unwrap();
+            }
+            blocks.into_inner()
+        });
+        assert_eq!(rx.iter().collect::<Vec<_>>(), expected);
+        let mut encoder = producer.join().unwrap();
+        assert_eq!(encoder.generate_encoded_block(None), reference.generate_encoded_block(None));
+        
+        // `&mut Encoder` works directly in `for` loops and combinators
+        let mut borrowed = Encoder::new_default(source_blocks, Some(3));
+        let seeds: Vec<i64> = (&mut borrowed).into_iter().zip(0..12).map(|(block, _)| block.0).collect();
+        assert_eq!(seeds, expected.iter().map(|block| block.0).collect::<Vec<_>>());
+    }
+    
+    #[test]
+    fn test_feedback() {
+        use crate::luby_transform::decoder::Decoder;
+        use crate::luby_transform::prng::systematic_seed;
+        
+        let k = 20;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 5]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(8));
+        let mut decoder: Decoder = Decoder::new_default(k, 1);
+        for (index, block) in source_blocks.iter().enumerate().take(15) {
+            decoder.add_encoded_block(systematic_seed(index), 1, block.clone());
+        }
+        let feedback = decoder.feedback();
+        assert_eq!(feedback, [0xff, 0x7f, 0x00]);
+        
+        // Every block now combines something the decoder is missing
+        encoder.apply_feedback(&feedback).unwrap();
+        while !decoder.is_complete() {
+            let (seed, d, indices, data) = encoder.generate_encoded_block(None).unwrap();
+            assert!(indices.iter().any(|&idx| idx >= 15));
+            decoder.add_encoded_block(seed, d, data);
+        }
+        assert_eq!(decoder.blocks_wasted(), 0);
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        assert_eq!(decoder.feedback(), [0xff, 0xff, 0x0f]);
+        
+        assert_eq!(encoder.apply_feedback(&[0xff]), Err(EncodeError::FeedbackLength { expected: 3, found: 1 }));
+        encoder.clear_feedback();
+        assert!(encoder.iter().take(50).any(|(_, _, indices, _)| indices.iter().all(|&idx| idx < 15)));
+    }
+    
+    #[test]
+    fn test_generate_repair_for() {
+        use crate::luby_transform::decoder::Decoder;
+        
+        let k = 30;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, -i]).collect();
+        let mut encoder = Encoder::new_default(source_blocks.clone(), Some(4));
+        let mut decoder: Decoder = Decoder::new_default(k, 2);
+        for (seed, d, _, data) in encoder.iter().take(k / 2) {
+            decoder.add_encoded_block(seed, d, data);
+        }
+        assert!(!decoder.is_complete());
+        
+        // Ask for exactly the blocks the feedback bitmap reports missing
+        let feedback = decoder.feedback();
+        let missing: Vec<usize> = (0..k).filter(|i| feedback[i / 8] & (1 << (i % 8)) == 0).collect();
+        let repair = encoder.generate_repair_for(&missing).unwrap();
+        assert_eq!(repair.len(), missing.len());
+        for (seed, d, indices, data) in repair {
+            assert_eq!(d, 1);
+            assert_eq!(data, source_blocks[*indices.iter().next().unwrap()]);
+            decoder.add_encoded_block(seed, d, data);
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        
+        // Duplicates collapse and the output is ordered
+        let repair = encoder.generate_repair_for(&[7, 2, 7]).unwrap();
+        assert_eq!(repair.iter().map(|(seed, _, _, _)| *seed).collect::<Vec<_>>(), [systematic_seed(2), systematic_seed(7)]);
+        assert_eq!(encoder.generate_repair_for(&[3, 30]), Err(EncodeError::IndexOutOfRange { index: 30, k: 30 }));
+        assert_eq!(encoder.generate_repair_for(&[]), Ok(Vec::new()));
+    }
 }
 
 // function blobToBase64(blob) {
@@ -0,0 +1,384 @@
+use core::fmt;
+
+use super::codec_id::CodecId;
+
+/// Errors reported by the Luby Transform codec
+///
+/// Every more specific error converts into this one, so callers mixing
+/// encoder, decoder and wire calls can propagate them all with `?`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LtError {
+    /// A serialized decoder snapshot could not be parsed
+    InvalidSnapshot(&'static str),
+    /// Invalid degree distribution or generator parameters
+    Dist(DistError),
+    /// An encoder could not produce a block
+    Encode(EncodeError),
+    /// Decoding failed or produced data that did not verify
+    Decode(DecodeError),
+    /// An encoded block could not be parsed from its wire format
+    Parse(ParseError),
+    /// An `LtConfig` was built from invalid parameters
+    Config(ConfigError),
+    /// A decoder rejected a malformed block
+    Block(BlockError),
+}
+
+/// Errors reported for invalid degree distribution or generator parameters
+#[derive(Clone, Debug, PartialEq)]
+pub enum DistError {
+    /// The distribution needs at least one source block
+    NoSourceBlocks,
+    /// The RSD failure probability must lie strictly between 0 and 1
+    DeltaOutOfRange(f64),
+    /// The RSD tuning constant must be positive
+    NonPositiveC(f64),
+    /// LCG parameters need `2 <= m <= 2^32` and `1 <= a < m`
+    InvalidLcgParams { a: i64, m: i64 },
+    /// The distribution only suits up to `max` source blocks
+    TooManyBlocks { k: usize, max: usize },
+    /// A target decoding probability must lie between 0 and 1
+    TargetProbOutOfRange(f64),
+}
+
+impl fmt::Display for DistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistError::NoSourceBlocks => write!(f, "the degree distribution needs at least one source block"),
+            DistError::DeltaOutOfRange(delta) => {
+                write!(f, "delta must lie strictly between 0 and 1, got {}", delta)
+            }
+            DistError::NonPositiveC(c) => write!(f, "c must be positive, got {}", c),
+            DistError::InvalidLcgParams { a, m } => {
+                write!(f, "invalid LCG parameters a = {}, m = {}", a, m)
+            }
+            DistError::TooManyBlocks { k, max } => {
+                write!(f, "the degree distribution supports at most {} source blocks, got {}", max, k)
+            }
+            DistError::TargetProbOutOfRange(prob) => {
+                write!(f, "target probability must lie between 0 and 1, got {}", prob)
+            }
+        }
+    }
+}
+
+impl core::error::Error for DistError {}
+
+/// Errors reported while generating encoded blocks
+#[derive(Clone, Debug, PartialEq)]
+pub enum EncodeError {
+    /// A selected source block differs in length from the first selected block
+    MismatchedBlockLength { index: usize, expected: usize, found: usize },
+    /// Replacement source blocks do not match the encoder's block count
+    SourceCountChanged { expected: usize, found: usize },
+    /// The encoder has no source blocks to combine
+    NoSourceBlocks,
+    /// A source block holds no symbols
+    EmptyBlock { index: usize },
+    /// A requested source index is not below the number of source blocks
+    IndexOutOfRange { index: usize, k: usize },
+    /// No seed was given and the encoder's generator was never seeded
+    Unseeded,
+    /// A feedback bitmap does not have one bit per source block
+    FeedbackLength { expected: usize, found: usize },
+    /// An explicit seed is not a state the generator can produce, e.g. 0 or
+    /// a negative seed reserved for systematic blocks
+    InvalidSeed { seed: i64 },
+    /// A fork's stream id is not below `STREAM_COUNT`
+    StreamOutOfRange { stream_id: u16 },
+    /// A shared distribution table was built for a different number of
+    /// source blocks
+    TableMismatch { table_k: usize, k: usize },
+    /// The precode redundancy is not a finite value between 0 and 1
+    InvalidRedundancy(f64),
+    /// The encoder's generator cannot use `SeedSequence` seeds, e.g. an LCG
+    /// with other constants than MINSTD
+    SeedSequenceUnsupported { source: &'static str },
+    /// A JS source block is not the typed array the binding expects
+    WrongBlockType { index: usize, expected: &'static str },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::MismatchedBlockLength { index, expected, found } => write!(
+                f,
+                "source block {} has length {} but {} was expected",
+                index, found, expected
+            ),
+            EncodeError::SourceCountChanged { expected, found } => write!(
+                f,
+                "expected {} source blocks but {} were given",
+                expected, found
+            ),
+            EncodeError::NoSourceBlocks => write!(f, "there are no source blocks to encode"),
+            EncodeError::EmptyBlock { index } => write!(f, "source block {} is empty", index),
+            EncodeError::IndexOutOfRange { index, k } => {
+                write!(f, "source index {} is out of range for {} blocks", index, k)
+            }
+            EncodeError::Unseeded => {
+                write!(f, "the encoder has no seed; pass one or call reset_seed first")
+            }
+            EncodeError::FeedbackLength { expected, found } => {
+                write!(f, "feedback has {} bytes but {} were expected", found, expected)
+            }
+            EncodeError::InvalidSeed { seed } => write!(f, "seed {} is not a valid generator state", seed),
+            EncodeError::StreamOutOfRange { stream_id } => write!(f, "stream {} is out of range", stream_id),
+            EncodeError::TableMismatch { table_k, k } => {
+                write!(f, "distribution table was built for {} source blocks but {} were given", table_k, k)
+            }
+            EncodeError::InvalidRedundancy(redundancy) => {
+                write!(f, "precode redundancy must lie between 0 and 1, got {}", redundancy)
+            }
+            EncodeError::SeedSequenceUnsupported { source } => {
+                write!(f, "the {} generator does not support indexed seeds or forked streams", source)
+            }
+            EncodeError::WrongBlockType { index, expected } => {
+                write!(f, "source block {} is not of type {}", index, expected)
+            }
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {}
+
+/// Errors reported while parsing an encoded block from its wire format
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The buffer is shorter than the fixed header
+    TruncatedHeader { len: usize },
+    /// The payload length field disagrees with the bytes that follow the header
+    LengthMismatch { declared: usize, available: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::TruncatedHeader { len } => {
+                write!(f, "buffer of {} bytes is too short for a block header", len)
+            }
+            ParseError::LengthMismatch { declared, available } => write!(
+                f,
+                "header declares {} payload bytes but {} are available",
+                declared, available
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Reasons `Decoder::try_add_encoded_block` rejects a malformed block
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockError {
+    /// The payload does not hold `block_size` symbols
+    LengthMismatch { seed: i64, expected: usize, found: usize },
+    /// The declared degree is 0 or exceeds the number of encoded blocks
+    DegreeOutOfRange { seed: i64, degree: usize, max: usize },
+    /// The declared degree differs from the one the seed implies
+    DegreeMismatch { seed: i64, declared: usize, expected: usize },
+    /// An explicitly given neighbour is not below the number of encoded blocks
+    IndexOutOfRange { seed: i64, index: usize, max: usize },
+    /// The seed is neither systematic nor a state the generator can produce
+    InvalidSeed { seed: i64 },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockError::LengthMismatch { seed, expected, found } => write!(
+                f,
+                "block with seed {}: {} symbols but block size is {}",
+                seed, found, expected
+            ),
+            BlockError::DegreeOutOfRange { seed, degree, max } => {
+                write!(f, "block with seed {}: degree {} outside 1..={}", seed, degree, max)
+            }
+            BlockError::DegreeMismatch { seed, declared, expected } => write!(
+                f,
+                "block with seed {}: degree {} but the seed implies {}",
+                seed, declared, expected
+            ),
+            BlockError::IndexOutOfRange { seed, index, max } => {
+                write!(f, "block with seed {}: neighbour {} outside 0..{}", seed, index, max)
+            }
+            BlockError::InvalidSeed { seed } => write!(f, "block with seed {}: not a valid generator state", seed),
+        }
+    }
+}
+
+impl core::error::Error for BlockError {}
+
+/// Errors reported by `LtConfigBuilder::build` and other constructors that
+/// take sizes
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// Blocks must hold at least one symbol
+    ZeroBlockSize,
+    /// Windows must hold at least one source block
+    ZeroWindowSize,
+    /// The degree distribution is invalid for `k`
+    Dist(DistError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroBlockSize => write!(f, "block_size must be positive"),
+            ConfigError::ZeroWindowSize => write!(f, "window_size must be positive"),
+            ConfigError::Dist(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {}
+
+/// Errors reported by `Codec::decode` and the decoder's length checks
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// The blocks ran out before every source block was recovered
+    Incomplete { decoded: usize, k: usize },
+    /// A block's payload does not have the length the header implies
+    BlockLengthMismatch { seed: i64, expected: usize, found: usize },
+    /// The header's degree distribution parameters are invalid
+    InvalidDistribution(DistError),
+    /// The decoded data does not match the header's source digest
+    Integrity(IntegrityError),
+    /// The header's codec id does not match the decoder built from it
+    Mismatch(MismatchError),
+    /// A data length does not fit `k` blocks of `block_size` symbols with
+    /// only the last one partly filled
+    DataLenOutOfRange { data_len: usize, k: usize, block_size: usize },
+    /// The block size given for the data is zero
+    ZeroBlockSize,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Incomplete { decoded, k } => write!(
+                f,
+                "only {} of {} source blocks could be decoded",
+                decoded, k
+            ),
+            DecodeError::BlockLengthMismatch { seed, expected, found } => write!(
+                f,
+                "block with seed {} has {} symbols but {} were expected",
+                seed, found, expected
+            ),
+            DecodeError::InvalidDistribution(err) => write!(f, "invalid header: {}", err),
+            DecodeError::Integrity(err) => write!(f, "{}", err),
+            DecodeError::Mismatch(err) => write!(f, "{}", err),
+            DecodeError::DataLenOutOfRange { data_len, k, block_size } => write!(
+                f,
+                "data length {} does not fit {} blocks of {} symbols",
+                data_len, k, block_size
+            ),
+            DecodeError::ZeroBlockSize => write!(f, "block_size must be positive"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Errors reported by `Decoder::verify`
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntegrityError {
+    /// Not every source block has been decoded yet
+    Incomplete { decoded: usize, k: usize },
+    /// No source digest was given to check against
+    NoDigest,
+    /// The decoded blocks hash to a different digest than the source
+    DigestMismatch { expected: [u8; 32], found: [u8; 32] },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Incomplete { decoded, k } => write!(
+                f,
+                "cannot verify: only {} of {} source blocks are decoded",
+                decoded, k
+            ),
+            IntegrityError::NoDigest => write!(f, "cannot verify: no source digest was set"),
+            IntegrityError::DigestMismatch { .. } => {
+                write!(f, "decoded data does not match the source digest")
+            }
+        }
+    }
+}
+
+impl core::error::Error for IntegrityError {}
+
+/// A block was stamped with a different `CodecId` than the decoder's
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MismatchError {
+    /// The decoder's own id
+    pub expected: CodecId,
+    /// The id the block arrived with
+    pub found: CodecId,
+}
+
+impl fmt::Display for MismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "encoder parameters {} do not match the decoder's {}; check k, the degree distribution and the generator",
+            self.found, self.expected
+        )
+    }
+}
+
+impl core::error::Error for MismatchError {}
+
+impl fmt::Display for LtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LtError::InvalidSnapshot(reason) => write!(f, "invalid decoder snapshot: {}", reason),
+            LtError::Dist(err) => write!(f, "{}", err),
+            LtError::Encode(err) => write!(f, "{}", err),
+            LtError::Decode(err) => write!(f, "{}", err),
+            LtError::Parse(err) => write!(f, "{}", err),
+            LtError::Config(err) => write!(f, "{}", err),
+            LtError::Block(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for LtError {}
+
+impl From<DistError> for LtError {
+    fn from(err: DistError) -> Self {
+        LtError::Dist(err)
+    }
+}
+
+impl From<EncodeError> for LtError {
+    fn from(err: EncodeError) -> Self {
+        LtError::Encode(err)
+    }
+}
+
+impl From<DecodeError> for LtError {
+    fn from(err: DecodeError) -> Self {
+        LtError::Decode(err)
+    }
+}
+
+impl From<ParseError> for LtError {
+    fn from(err: ParseError) -> Self {
+        LtError::Parse(err)
+    }
+}
+
+impl From<ConfigError> for LtError {
+    fn from(err: ConfigError) -> Self {
+        LtError::Config(err)
+    }
+}
+
+impl From<BlockError> for LtError {
+    fn from(err: BlockError) -> Self {
+        LtError::Block(err)
+    }
+}
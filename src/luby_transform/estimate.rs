@@ -0,0 +1,108 @@
+use super::compat;
+use super::decoder::Decoder;
+use super::encoder::Encoder;
+use super::error::DistError;
+use super::prng::{gen_rho, gen_tau, validate_params};
+#[cfg(not(feature = "std"))]
+use super::compat::{vec, Vec};
+
+/// Recommends how many encoded blocks to send so that `k` source blocks
+/// decode with probability at least `target_prob`
+///
+/// Uses Luby's bound for the Robust Soliton Distribution: `k * beta` blocks,
+/// where `beta` is the RSD normalizer, suffice with failure probability at
+/// most `delta`. That is `k + O(sqrt(k) * ln^2(k / delta))`. The bound is
+/// evaluated at the smaller of `delta` and `1 - target_prob`, so asking for
+/// more certainty than `delta` alone gives costs extra blocks.
+///
+/// Returns 0 for `k == 0`. Fails if `delta` or `c` are rejected by
+/// `validate_params` or `target_prob` does not lie in `0..=1`.
+pub fn estimate_blocks_needed(k: usize, delta: f64, c: f64, target_prob: f64) -> Result<usize, DistError> {
+    if !(0.0..=1.0).contains(&target_prob) {
+        return Err(DistError::TargetProbOutOfRange(target_prob));
+    }
+    if k == 0 {
+        return Ok(0);
+    }
+    validate_params(k, delta, c)?;
+
+    let failure = (1.0 - target_prob).clamp(f64::MIN_POSITIVE, 1.0);
+    let delta = delta.min(failure);
+    let s = c * compat::ln(k as f64 / delta) * compat::sqrt(k as f64);
+    let beta = gen_rho(k).iter().sum::<f64>() + gen_tau(s, k, delta).iter().sum::<f64>();
+
+    Ok(compat::ceil(k as f64 * beta) as usize)
+}
+
+/// Block counts observed by `estimate_blocks_empirical`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BlockEstimate {
+    /// Mean number of blocks received before decoding completed
+    pub mean: f64,
+    /// Median block count
+    pub p50: usize,
+    /// 95th percentile block count
+    pub p95: usize,
+}
+
+/// Measures how many encoded blocks the default `Encoder`/`Decoder` pair
+/// needs to recover `k` source blocks, over `trials` runs with different
+/// seeds
+pub fn estimate_blocks_empirical(k: usize, trials: usize) -> BlockEstimate {
+    if k == 0 || trials == 0 {
+        return BlockEstimate::default();
+    }
+
+    let mut counts: Vec<usize> = (0..trials)
+        .map(|trial| {
+            let source_blocks = (0..k as i32).map(|i| vec![i]).collect();
+            let mut encoder = Encoder::new_default(source_blocks, Some(trial as i64 + 1));
+            let mut decoder = Decoder::new_default(k, 1);
+
+            let mut sent = 0;
+            while !decoder.is_complete() {
+                let Ok((seed, degree, _, data)) = encoder.generate_encoded_block(None) else {
+                    break;
+                };
+                decoder.add_encoded_block(seed, degree, data);
+                sent += 1;
+            }
+            sent
+        })
+        .collect();
+    counts.sort_unstable();
+
+    // Nearest-rank percentile
+    let percentile = |p: usize| counts[(p * trials).div_ceil(100).max(1) - 1];
+
+    BlockEstimate {
+        mean: counts.iter().sum::<usize>() as f64 / trials as f64,
+        p50: percentile(50),
+        p95: percentile(95),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::luby_transform::prng::{DEFAULT_C, DEFAULT_DELTA};
+
+    #[test]
+    fn test_empirical_close_to_analytic() {
+        let k = 50;
+        let empirical = estimate_blocks_empirical(k, 100);
+
+        // The bound at `delta` should be in the neighbourhood of the mean,
+        // and the bound at 95% should cover the observed 95th percentile
+        let typical = estimate_blocks_needed(k, DEFAULT_DELTA, DEFAULT_C, 0.5).unwrap() as f64;
+        assert!((empirical.mean - typical).abs() / typical < 0.3, "{empirical:?} vs {typical}");
+        assert!(empirical.p95 <= estimate_blocks_needed(k, DEFAULT_DELTA, DEFAULT_C, 0.95).unwrap());
+
+        assert!(empirical.p50 >= k && empirical.p50 <= empirical.p95);
+        assert_eq!(estimate_blocks_needed(0, DEFAULT_DELTA, DEFAULT_C, 0.9), Ok(0));
+        assert_eq!(estimate_blocks_needed(k, 0.0, DEFAULT_C, 0.9), Err(DistError::DeltaOutOfRange(0.0)));
+        assert!(matches!(estimate_blocks_needed(k, f64::NAN, DEFAULT_C, 0.9), Err(DistError::DeltaOutOfRange(_))));
+        assert!(matches!(estimate_blocks_needed(k, DEFAULT_DELTA, DEFAULT_C, f64::NAN), Err(DistError::TargetProbOutOfRange(_))));
+        assert_eq!(estimate_blocks_empirical(0, 10), BlockEstimate::default());
+    }
+}
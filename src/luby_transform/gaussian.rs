@@ -0,0 +1,140 @@
+use alloc::collections::BTreeSet;
+use super::xor::{xor_into, XorElement};
+#[cfg(not(feature = "std"))]
+use super::compat::{vec, Vec};
+
+/// Result of solving a system of XOR equations over GF(2)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution<T = i32> {
+    /// Solved unknowns as (source index, data), sorted by index
+    pub solved: Vec<(usize, Vec<T>)>,
+    /// Unknowns the system does not determine, sorted by index
+    pub missing: Vec<usize>,
+}
+
+/// Solves a system of XOR equations over GF(2) using Gauss-Jordan elimination
+///
+/// Each equation is a set of source indices together with the XOR of the
+/// corresponding source blocks. Indices not listed in `unknowns` must already
+/// have been XORed out of the equation data; they are ignored here.
+///
+/// Pivot selection is deterministic: columns are processed in ascending order
+/// of source index, and for each column the first remaining equation (in the
+/// order given) with a non-zero coefficient becomes the pivot row. The same
+/// input therefore always yields the same solution and the same `missing`
+/// set, independent of platform or hash ordering.
+pub fn solve<T: XorElement>(unknowns: &[usize], equations: Vec<(BTreeSet<usize>, Vec<T>)>) -> Solution<T> {
+    let mut columns: Vec<usize> = unknowns.to_vec();
+    columns.sort_unstable();
+    columns.dedup();
+
+    let words = columns.len().div_ceil(64);
+    let mut rows: Vec<(Vec<u64>, Vec<T>)> = equations
+        .into_iter()
+        .map(|(deps, data)| {
+            let mut bits = vec![0u64; words];
+            for dep in deps {
+                if let Ok(col) = columns.binary_search(&dep) {
+                    bits[col / 64] |= 1 << (col % 64);
+                }
+            }
+            (bits, data)
+        })
+        .collect();
+
+    let mut pivots: Vec<(usize, usize)> = Vec::new(); // (column, row)
+    let mut next_row = 0;
+
+    for col in 0..columns.len() {
+        let (word, mask) = (col / 64, 1u64 << (col % 64));
+
+        // First row at or after `next_row` with a non-zero coefficient
+        let Some(pivot) = (next_row..rows.len()).find(|&r| rows[r].0[word] & mask != 0) else {
+            continue;
+        };
+        rows.swap(next_row, pivot);
+
+        // Eliminate this column from every other row
+        let (pivot_bits, pivot_data) = rows[next_row].clone();
+        for (r, (bits, data)) in rows.iter_mut().enumerate() {
+            if r != next_row && bits[word] & mask != 0 {
+                for (b, p) in bits.iter_mut().zip(&pivot_bits) {
+                    *b ^= p;
+                }
+                xor_into(data, &pivot_data);
+            }
+        }
+
+        pivots.push((col, next_row));
+        next_row += 1;
+    }
+
+    // A pivot row determines its column only if no free column remains in it
+    let mut solved = Vec::new();
+    for (col, row) in pivots {
+        let (bits, data) = &rows[row];
+        if bits.iter().map(|w| w.count_ones()).sum::<u32>() == 1 {
+            solved.push((columns[col], data.clone()));
+        }
+    }
+
+    let missing = columns
+        .iter()
+        .filter(|idx| !solved.iter().any(|(s, _)| s == *idx))
+        .cloned()
+        .collect();
+
+    Solution { solved, missing }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eq(deps: &[usize], data: Vec<i32>) -> (BTreeSet<usize>, Vec<i32>) {
+        (deps.iter().cloned().collect(), data)
+    }
+
+    #[test]
+    fn test_solve_deterministic() {
+        // x0 = [1, 2], x1 = [3, 4], x2 = [5, 6]
+        let system = || {
+            vec![
+                eq(&[0, 1], vec![1 ^ 3, 2 ^ 4]),
+                eq(&[1, 2], vec![3 ^ 5, 4 ^ 6]),
+                eq(&[0, 1, 2], vec![1 ^ 3 ^ 5, 2 ^ 4 ^ 6]),
+            ]
+        };
+
+        let first = solve(&[0, 1, 2], system());
+        assert_eq!(
+            first.solved,
+            vec![(0, vec![1, 2]), (1, vec![3, 4]), (2, vec![5, 6])]
+        );
+        assert!(first.missing.is_empty());
+
+        for _ in 0..10 {
+            assert_eq!(solve(&[0, 1, 2], system()), first);
+        }
+    }
+
+    #[test]
+    fn test_solve_underdetermined() {
+        // x0 ^ x1 and x1 ^ x2 leave one degree of freedom, x3 is known directly
+        let system = || {
+            vec![
+                eq(&[0, 1], vec![7]),
+                eq(&[1, 2], vec![9]),
+                eq(&[3], vec![11]),
+            ]
+        };
+
+        let first = solve(&[0, 1, 2, 3], system());
+        assert_eq!(first.solved, vec![(3, vec![11])]);
+        assert_eq!(first.missing, vec![0, 1, 2]);
+
+        for _ in 0..10 {
+            assert_eq!(solve(&[0, 1, 2, 3], system()).missing, first.missing);
+        }
+    }
+}
@@ -1,3 +1,18 @@
 pub mod prng;
 pub mod encoder;
-pub mod decoder;
\ No newline at end of file
+pub mod decoder;
+pub mod state;
+pub mod gaussian;
+pub mod precode;
+pub mod bytes;
+pub mod error;
+pub mod compat;
+pub mod xor;
+pub mod crc;
+pub mod codec_id;
+pub mod sha256;
+pub mod estimate;
+pub mod windowed;
+pub mod tuner;
+pub mod config;
+pub(crate) mod wire;
\ No newline at end of file
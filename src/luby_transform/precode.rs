@@ -0,0 +1,135 @@
+use super::error::EncodeError;
+use super::xor::{xor_into, XorElement};
+use super::compat;
+use alloc::collections::BTreeSet;
+#[cfg(not(feature = "std"))]
+use super::compat::{vec, Vec};
+
+/// Sparse LDPC-style precode applied before LT encoding
+///
+/// The precode extends `k` source blocks with `p` parity blocks, each the
+/// XOR of a few source blocks. The LT layer then encodes all `k + p`
+/// intermediate blocks. On the receiving side every parity relation is an
+/// extra XOR equation with an all-zero right-hand side, which helps the
+/// decoder recover source blocks from fewer encoded blocks.
+///
+/// Parity assignment follows the LDPC construction of RFC 5053: source
+/// block `i` contributes to three parity blocks spaced by a stride derived
+/// from `i`, so the structure depends only on `k` and `p`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Precode {
+    k: usize,
+    parity_deps: Vec<BTreeSet<usize>>, // Parity j -> source indices it combines
+}
+
+impl Precode {
+    /// Creates a precode with `ceil(redundancy * k)` parity blocks
+    ///
+    /// `redundancy` must lie in `0..=1`; NaN and infinities are rejected.
+    pub fn new(k: usize, redundancy: f64) -> Result<Self, EncodeError> {
+        if !(0.0..=1.0).contains(&redundancy) {
+            return Err(EncodeError::InvalidRedundancy(redundancy));
+        }
+        let p = compat::ceil(redundancy * k as f64) as usize;
+        Ok(Self::with_parity_count(k, p))
+    }
+
+    /// Creates a precode with exactly `p` parity blocks
+    pub fn with_parity_count(k: usize, p: usize) -> Self {
+        let mut parity_deps = vec![BTreeSet::new(); p];
+
+        if p > 0 {
+            for i in 0..k {
+                let a = if p > 1 { 1 + (i / p) % (p - 1) } else { 0 };
+                let mut b = i % p;
+                for _ in 0..3 {
+                    // XOR semantics: contributing twice cancels out
+                    if !parity_deps[b].remove(&i) {
+                        parity_deps[b].insert(i);
+                    }
+                    b = (b + a) % p;
+                }
+            }
+        }
+
+        Self { k, parity_deps }
+    }
+
+    /// Number of source blocks the precode was built for
+    pub fn source_count(&self) -> usize {
+        self.k
+    }
+
+    /// Number of parity blocks the precode adds
+    pub fn parity_count(&self) -> usize {
+        self.parity_deps.len()
+    }
+
+    /// Returns the source blocks followed by their parity blocks
+    pub fn expand<T: XorElement>(&self, source_blocks: &[Vec<T>]) -> Result<Vec<Vec<T>>, EncodeError> {
+        let block_len = source_blocks.first().map_or(0, Vec::len);
+        let mut expanded = source_blocks.to_vec();
+
+        for deps in &self.parity_deps {
+            let mut parity = vec![T::ZERO; block_len];
+            for &idx in deps {
+                if source_blocks[idx].len() != block_len {
+                    return Err(EncodeError::MismatchedBlockLength {
+                        index: idx,
+                        expected: block_len,
+                        found: source_blocks[idx].len(),
+                    });
+                }
+                xor_into(&mut parity, &source_blocks[idx]);
+            }
+            expanded.push(parity);
+        }
+
+        Ok(expanded)
+    }
+
+    /// Returns, for each parity block, the set of intermediate indices whose
+    /// XOR is zero (the parity's sources plus the parity block itself)
+    pub fn constraints(&self) -> Vec<BTreeSet<usize>> {
+        self.parity_deps
+            .iter()
+            .enumerate()
+            .map(|(j, deps)| {
+                let mut constraint = deps.clone();
+                constraint.insert(self.k + j);
+                constraint
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constraints_hold() {
+        let k = 20;
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 7, i ^ 0x55]).collect();
+        let precode = Precode::new(k, 0.2).unwrap();
+        assert_eq!(precode.parity_count(), 4);
+
+        let expanded = precode.expand(&source_blocks).unwrap();
+        assert_eq!(expanded.len(), k + 4);
+        for constraint in precode.constraints() {
+            let xor = constraint.iter().fold(vec![0, 0], |acc, &i| {
+                acc.iter().zip(&expanded[i]).map(|(a, b)| a ^ b).collect()
+            });
+            assert_eq!(xor, vec![0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_invalid_redundancy() {
+        for redundancy in [f64::NAN, f64::INFINITY, -0.5, 1.5] {
+            assert!(matches!(Precode::new(10, redundancy), Err(EncodeError::InvalidRedundancy(_))));
+        }
+        assert_eq!(Precode::new(10, 0.0).unwrap().parity_count(), 0);
+        assert_eq!(Precode::new(10, 1.0).unwrap().parity_count(), 10);
+    }
+}
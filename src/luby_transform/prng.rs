@@ -1,42 +1,163 @@
-use std::collections::HashSet;
+use super::compat;
+use alloc::collections::BTreeSet;
+use super::error::DistError;
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use super::compat::{vec, Box, Vec};
 
 // Default parameters
 pub const DEFAULT_C: f64 = 0.1;
 pub const DEFAULT_DELTA: f64 = 0.5;
+/// Seed of the first block from an encoder created without one
+pub const DEFAULT_SEED: i64 = 1;
 
 // Parameters for Pseudorandom Number Generator
 const PRNG_A: i64 = 16807;
 const PRNG_M: i64 = (1 << 31) - 1;
-const PRNG_MAX_RAND: i64 = PRNG_M - 1;
+
+/// Returns the reserved seed that marks a systematic block carrying
+/// source block `index` verbatim
+///
+/// Negative seeds never occur as PRNG states, so seed `-(index + 1)`
+/// identifies the systematic block for `index`.
+pub fn systematic_seed(index: usize) -> i64 {
+    -(index as i64) - 1
+}
+
+/// Returns the source index carried by a systematic block seed, or `None`
+/// for a regular PRNG seed
+pub fn systematic_index(seed: i64) -> Option<usize> {
+    (seed < 0).then(|| (-(seed + 1)) as usize)
+}
+
+/// MINSTD steps between consecutive `SeedSequence` seeds
+const SEED_STRIDE: u64 = 1 << 20;
+
+/// Number of running streams `SeedSequence::stream` splits the cycle into
+///
+/// `3^2 * 7 * 31`, a divisor of half the MINSTD period `2^31 - 2`, so the
+/// streams tile the cycle exactly and each stride is even.
+pub const STREAM_COUNT: u16 = 1953;
+
+/// MINSTD steps between the starts of consecutive `SeedSequence` streams
+const STREAM_STRIDE: u64 = (PRNG_M as u64 - 1) / STREAM_COUNT as u64;
+
+/// Derives the seed of encoded block `i` directly from a root seed
+///
+/// Seed `i` is the MINSTD state `(i + 1) * 2^20` steps after the root,
+/// computed by modular exponentiation, so any block can be reproduced from
+/// its index alone. The stride keeps the PRNG draws of neighbouring blocks
+/// from overlapping. Seeds lie in `1..2^31 - 1`, never colliding with the
+/// reserved systematic seeds or the precode's seed 0, and the first
+/// `2^30 - 1` are distinct. The seeds are MINSTD states, so generators
+/// with other constants cannot use them; see
+/// `RandomSource::supports_seed_sequence`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedSequence {
+    root: i64,
+}
+
+impl SeedSequence {
+    /// Creates a sequence rooted at `root_seed`, reduced to a valid MINSTD
+    /// state (a root of 0 becomes 1)
+    pub fn new(root_seed: i64) -> Self {
+        let root = root_seed.rem_euclid(PRNG_M);
+        Self { root: if root == 0 { 1 } else { root } }
+    }
+    
+    /// The normalized root seed
+    pub fn root(&self) -> i64 {
+        self.root
+    }
+    
+    /// Returns the seed of block `index`
+    pub fn nth(&self, index: usize) -> i64 {
+        let jump = pow_mod(PRNG_A, SEED_STRIDE);
+        self.root * pow_mod(jump, index as u64 + 1) % PRNG_M
+    }
+    
+    /// Returns the state running stream `stream_id` starts from, or `None`
+    /// unless `stream_id < STREAM_COUNT`
+    ///
+    /// Stream `s` starts `s * STREAM_STRIDE + 1` MINSTD steps after the root,
+    /// so the streams split the generator's cycle into disjoint stretches of
+    /// 1,099,582 draws, the last one ending where stream 0 starts. Every
+    /// `nth` seed lies an even number of steps after the root and every
+    /// stream start an odd number, so no stream starts on an indexed seed.
+    /// Later seeds of a running stream are not kept apart from indexed ones.
+    /// See `Encoder::fork`.
+    pub fn stream(&self, stream_id: u16) -> Option<i64> {
+        (stream_id < STREAM_COUNT).then(|| self.root * pow_mod(PRNG_A, stream_id as u64 * STREAM_STRIDE + 1) % PRNG_M)
+    }
+}
+
+/// Computes `base^exp mod PRNG_M`
+fn pow_mod(base: i64, mut exp: u64) -> i64 {
+    let (mut result, mut base) = (1, base % PRNG_M);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % PRNG_M;
+        }
+        base = base * base % PRNG_M;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Checks that `k`, `delta` and `c` give a well-defined Robust Soliton
+/// Distribution
+///
+/// Degenerate values make `gen_mu` produce NaNs or divide by a zero
+/// normalizer, which would only show up later as a decode that never
+/// completes.
+pub fn validate_params(k: usize, delta: f64, c: f64) -> Result<(), DistError> {
+    if k == 0 {
+        return Err(DistError::NoSourceBlocks);
+    }
+    if !(delta > 0.0 && delta < 1.0) {
+        return Err(DistError::DeltaOutOfRange(delta));
+    }
+    if !(c > 0.0 && c.is_finite()) {
+        return Err(DistError::NonPositiveC(c));
+    }
+    Ok(())
+}
 
 /// Generates the Robust part of the RSD (tau)
+///
+/// Always returns exactly `k` non-negative entries. With `k <= 1` every
+/// block has degree 1 and tau is all zeros. Otherwise the spike sits at
+/// degree `k / s`, clamped to `2..=max(2, k - 1)`, which is at most `k`; for
+/// `k == 2` that is degree 2. When `s < delta` the spike term
+/// `s / k * ln(s / delta)` would be negative and is dropped.
 pub fn gen_tau(s: f64, k: usize, delta: f64) -> Vec<f64> {
-    // Ensure pivot is at least 2 to avoid overflow and negative values
-    let pivot = std::cmp::max(2, std::cmp::min((k as f64 / s).floor() as usize, k - 1));
-    let mut tau = Vec::with_capacity(k);
+    let mut tau = vec![0.0; k];
+    if k <= 1 {
+        return tau;
+    }
+    
+    // Ensure pivot is at least 2 and never past the last degree
+    let pivot = core::cmp::max(2, core::cmp::min(compat::floor(k as f64 / s) as usize, k - 1));
     
     // First part: 1 to pivot-1
     for d in 1..pivot {
-        tau.push(s / (k as f64) * (1.0 / d as f64));
+        tau[d - 1] = s / (k as f64) * (1.0 / d as f64);
     }
     
-    // Pivot point - make sure we don't exceed k elements
-    if tau.len() < k {
-        tau.push(s / (k as f64) * (s / delta).ln());
-    }
-    
-    // Remaining zeros - ensure we don't create a negative size
-    let remaining = k.saturating_sub(tau.len());
-    if remaining > 0 {
-        tau.extend(vec![0.0; remaining]);
-    }
+    // Pivot point
+    tau[pivot - 1] = (s / (k as f64) * compat::ln(s / delta)).max(0.0);
     
     tau
 }
 
 /// Generates the Ideal Soliton Distribution (rho)
+///
+/// Returns exactly `k` entries; empty for `k == 0`.
 pub fn gen_rho(k: usize) -> Vec<f64> {
     let mut rho = Vec::with_capacity(k);
+    if k == 0 {
+        return rho;
+    }
     
     // First element is 1/K
     rho.push(1.0 / k as f64);
@@ -51,7 +172,7 @@ pub fn gen_rho(k: usize) -> Vec<f64> {
 
 /// Generates the Robust Soliton Distribution (mu)
 pub fn gen_mu(k: usize, delta: f64, c: f64) -> Vec<f64> {
-    let s = c * ((k as f64 / delta).ln()) * (k as f64).sqrt();
+    let s = c * compat::ln(k as f64 / delta) * compat::sqrt(k as f64);
     let tau = gen_tau(s, k, delta);
     let rho = gen_rho(k);
     
@@ -79,86 +200,614 @@ pub fn gen_rsd_cdf(k: usize, delta: f64, c: f64) -> Vec<f64> {
     cdf
 }
 
+/// Generates the CDF of the Ideal Soliton Distribution for sampling
+pub fn gen_ideal_cdf(k: usize) -> Vec<f64> {
+    let rho = gen_rho(k);
+    let mut cdf = Vec::with_capacity(k);
+    let mut sum = 0.0;
+    
+    for &prob in &rho {
+        sum += prob;
+        cdf.push(sum);
+    }
+    
+    cdf
+}
+
+/// Largest `k` accepted for `DegreeDistribution::Dense`
+///
+/// Dense blocks average `k / 2` source blocks, so encoding costs grow with
+/// `k` per block and decoding relies on Gaussian elimination, cubic in `k`.
+pub const DENSE_MAX_K: usize = 256;
+
+/// Generates the CDF of the degree of a uniformly random non-empty subset
+/// of `k` blocks, proportional to the binomial coefficient `C(k, d)`
+///
+/// Weights are built outwards from the middle degree, where they peak, so
+/// large `k` only underflows the negligible tails.
+pub fn gen_dense_cdf(k: usize) -> Vec<f64> {
+    let mut weights = vec![0.0; k];
+    if k == 0 {
+        return weights;
+    }
+    let mid = k.div_ceil(2);
+    weights[mid - 1] = 1.0;
+    for d in mid + 1..=k {
+        // C(k, d) = C(k, d - 1) * (k - d + 1) / d
+        weights[d - 1] = weights[d - 2] * (k - d + 1) as f64 / d as f64;
+    }
+    for d in (1..mid).rev() {
+        // C(k, d) = C(k, d + 1) * (d + 1) / (k - d)
+        weights[d - 1] = weights[d] * (d + 1) as f64 / (k - d) as f64;
+    }
+    
+    let total: f64 = weights.iter().sum();
+    let mut sum = 0.0;
+    for weight in &mut weights {
+        sum += *weight / total;
+        *weight = sum;
+    }
+    weights
+}
+
+/// Degree distribution used to pick how many source blocks an encoded
+/// block combines
+///
+/// Encoder and decoder must use the same distribution to agree on the
+/// dependencies behind each seed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DegreeDistribution {
+    /// Ideal Soliton Distribution (rho only)
+    Ideal,
+    /// Robust Soliton Distribution with failure probability `delta` and
+    /// tuning constant `c`
+    Robust { delta: f64, c: f64 },
+    /// Degrees of uniformly random non-empty subsets, see `gen_dense_cdf`
+    ///
+    /// Not an optimised soliton variant but random linear coding: blocks
+    /// combine about `k / 2` source blocks, so this suits short messages
+    /// only and `validate` rejects `k` above `DENSE_MAX_K`. With the
+    /// decoder's Gaussian elimination fallback it completes about 1.6 blocks
+    /// past `k` on average, where the robust soliton needs over 10% extra
+    /// below `k = 50`. Peeling alone rarely finishes.
+    Dense,
+}
+
+impl Default for DegreeDistribution {
+    fn default() -> Self {
+        DegreeDistribution::Robust {
+            delta: DEFAULT_DELTA,
+            c: DEFAULT_C,
+        }
+    }
+}
+
+impl DegreeDistribution {
+    /// Checks the distribution parameters for `k` source blocks, see
+    /// `validate_params`
+    pub fn validate(&self, k: usize) -> Result<(), DistError> {
+        match *self {
+            DegreeDistribution::Ideal | DegreeDistribution::Dense if k == 0 => Err(DistError::NoSourceBlocks),
+            DegreeDistribution::Dense if k > DENSE_MAX_K => Err(DistError::TooManyBlocks { k, max: DENSE_MAX_K }),
+            DegreeDistribution::Ideal | DegreeDistribution::Dense => Ok(()),
+            DegreeDistribution::Robust { delta, c } => validate_params(k, delta, c),
+        }
+    }
+    
+    /// Builds the CDF of this distribution over degrees `1..=k`
+    pub fn cdf(&self, k: usize) -> Vec<f64> {
+        match *self {
+            DegreeDistribution::Ideal => gen_ideal_cdf(k),
+            DegreeDistribution::Dense => gen_dense_cdf(k),
+            DegreeDistribution::Robust { delta, c } => gen_rsd_cdf(k, delta, c),
+        }
+    }
+}
+
+/// Source of the uniform random numbers behind `PRNG`
+///
+/// A block's seed is the generator state at the start of that block, so
+/// `seed` followed by `set_seed` must reproduce the same outputs. Seeds must
+/// stay non-negative since negative seeds mark systematic blocks. Encoder and
+/// decoder have to use the same generator; `name` identifies it in decoder
+/// snapshots. Sources must be `Send` so encoders can move to other threads.
+pub trait RandomSource: Send {
+    /// Advances the generator and returns an output in `0..=self.max_output()`
+    fn next_u32(&mut self) -> u32;
+    
+    /// Largest value `next_u32` can return
+    fn max_output(&self) -> u32;
+    
+    /// Sets the generator state
+    fn set_seed(&mut self, seed: i64);
+    
+    /// Checks whether `seed` is a state encoders can produce, so a block
+    /// carrying any other seed is corrupt
+    ///
+    /// Negative seeds are reserved for systematic blocks.
+    fn is_valid_seed(&self, seed: i64) -> bool {
+        seed >= 0
+    }
+    
+    /// Returns the current state as a seed, or `None` before `set_seed`
+    fn seed(&self) -> Option<i64>;
+    
+    /// Checks whether the MINSTD states `SeedSequence` derives are valid
+    /// seeds for this generator
+    ///
+    /// Generators with a different state space must return `false`, which
+    /// makes `Encoder::generate_block_at` and `Encoder::fork` fail.
+    fn supports_seed_sequence(&self) -> bool {
+        true
+    }
+    
+    /// Stable name of the generator
+    fn name(&self) -> &'static str;
+    
+    /// Returns a boxed copy of the generator, state included
+    fn clone_box(&self) -> Box<dyn RandomSource>;
+}
+
+/// Multiplier and modulus of a Lehmer LCG, `state = a * state mod m`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LcgParams {
+    pub a: i64,
+    pub m: i64,
+}
+
+impl LcgParams {
+    /// The MINSTD constants used by default
+    pub const MINSTD: LcgParams = LcgParams { a: PRNG_A, m: PRNG_M };
+    
+    /// Checks that `2 <= m <= 2^32` and `1 <= a < m`, so every output fits
+    /// in a `u32`
+    pub fn validate(&self) -> Result<(), DistError> {
+        if (2..=1 << 32).contains(&self.m) && (1..self.m).contains(&self.a) {
+            Ok(())
+        } else {
+            Err(DistError::InvalidLcgParams { a: self.a, m: self.m })
+        }
+    }
+}
+
+impl Default for LcgParams {
+    fn default() -> Self {
+        Self::MINSTD
+    }
+}
+
+/// A Lehmer generator, by default with the MINSTD constants; the default
+/// `RandomSource`
+#[derive(Clone, Debug, Default)]
+pub struct Minstd {
+    state: Option<i64>,
+    params: LcgParams,
+}
+
+impl Minstd {
+    /// Creates a generator with other constants, to interoperate with
+    /// implementations that do not use MINSTD
+    ///
+    /// Fails if `params` is rejected by `LcgParams::validate`. Only the
+    /// MINSTD constants survive `Decoder::snapshot`/`restore`.
+    pub fn with_params(params: LcgParams) -> Result<Self, DistError> {
+        params.validate()?;
+        Ok(Self { state: None, params })
+    }
+    
+    /// The constants this generator uses
+    pub fn params(&self) -> LcgParams {
+        self.params
+    }
+}
+
+impl RandomSource for Minstd {
+    fn next_u32(&mut self) -> u32 {
+        if let Some(current_state) = self.state {
+            let LcgParams { a, m } = self.params;
+            let next = (a as i128 * current_state as i128 % m as i128) as i64;
+            self.state = Some(next);
+            // States lie in [1, m - 1] for a prime modulus
+            (next - 1).max(0) as u32
+        } else {
+            panic!("PRNG state not initialized. Call set_seed first.")
+        }
+    }
+    
+    fn max_output(&self) -> u32 {
+        (self.params.m - 2) as u32
+    }
+    
+    /// Reduces `seed` modulo `m`, remapping the fixed point 0, which would
+    /// repeat the same degenerate block forever, to `DEFAULT_SEED`
+    fn set_seed(&mut self, seed: i64) {
+        let state = seed.rem_euclid(self.params.m);
+        self.state = Some(if state == 0 { DEFAULT_SEED } else { state });
+    }
+    
+    fn is_valid_seed(&self, seed: i64) -> bool {
+        (1..self.params.m).contains(&seed)
+    }
+    
+    fn seed(&self) -> Option<i64> {
+        self.state
+    }
+    
+    /// Only the MINSTD constants share `SeedSequence`'s state space
+    fn supports_seed_sequence(&self) -> bool {
+        self.params == LcgParams::MINSTD
+    }
+    
+    fn name(&self) -> &'static str {
+        if self.params == LcgParams::MINSTD { "minstd" } else { "lcg" }
+    }
+    
+    fn clone_box(&self) -> Box<dyn RandomSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// A SplitMix generator over a 63-bit counter
+///
+/// Each step adds an odd constant to the counter modulo 2^63, giving a
+/// period of 2^63 and keeping every state a valid non-negative seed. Outputs
+/// are the high bits of the SplitMix64 finalizer applied to the counter, so
+/// they have none of the low-bit regularities of an LCG.
+#[derive(Clone, Debug, Default)]
+pub struct SplitMix {
+    state: Option<u64>,
+}
+
+impl RandomSource for SplitMix {
+    fn next_u32(&mut self) -> u32 {
+        let Some(current_state) = self.state else {
+            panic!("PRNG state not initialized. Call set_seed first.")
+        };
+        let next = current_state.wrapping_add(0x9e37_79b9_7f4a_7c15) & (u64::MAX >> 1);
+        self.state = Some(next);
+        
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        ((z ^ (z >> 31)) >> 32) as u32
+    }
+    
+    fn max_output(&self) -> u32 {
+        u32::MAX
+    }
+    
+    fn set_seed(&mut self, seed: i64) {
+        self.state = Some(seed as u64 & (u64::MAX >> 1));
+    }
+    
+    fn seed(&self) -> Option<i64> {
+        self.state.map(|state| state as i64)
+    }
+    
+    fn name(&self) -> &'static str {
+        "splitmix"
+    }
+    
+    fn clone_box(&self) -> Box<dyn RandomSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// Returns a fresh instance of the built-in generator called `name`
+pub fn random_source_by_name(name: &str) -> Option<Box<dyn RandomSource>> {
+    match name {
+        "minstd" => Some(Box::new(Minstd::default())),
+        "splitmix" => Some(Box::new(SplitMix::default())),
+        _ => None,
+    }
+}
+
+/// Version of the algorithm that turns a block's seed into its neighbours,
+/// part of the wire contract and of every `CodecId`
+///
+/// From the generator state set to the seed, version 1 draws:
+///
+/// 1. The degree, from one output `r`: `p = (r + 1) / (max_output + 1)` and
+///    the degree is the smallest `d` with `cdf[d - 1] > p`, or `k` if there
+///    is none. With `k <= 1` the draw is still made and the degree is 1.
+/// 2. The neighbours, one output at a time: outputs at or above the largest
+///    multiple of `k` not exceeding `max_output + 1` are rejected, others
+///    give index `r % k`, and repeated indices are skipped until `d`
+///    distinct ones are found.
+///
+/// Any change to these steps that alters the neighbours of some seed must
+/// bump this version, so peers on different versions see different ids.
+pub const SAMPLING_VERSION: u8 = 1;
+
+/// The CDF of a degree distribution for `k` source blocks
+///
+/// Building the CDF is O(k). Encoders and decoders with the same parameters
+/// can share one table through an `Arc` (see `PRNG::with_table`,
+/// `Encoder::with_table` and `Decoder::with_table`) instead of each
+/// computing their own. With `std`, every `PRNG` constructor that builds
+/// its own table goes through `DistributionTable::shared`, so this happens
+/// automatically.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistributionTable {
+    k: usize,
+    distribution: DegreeDistribution,
+    cdf: Vec<f64>,
+}
+
+impl DistributionTable {
+    /// Builds the Robust Soliton table for `k`, `delta` and `c`
+    ///
+    /// Fails if the parameters are rejected by `validate_params`.
+    pub fn new(k: usize, delta: f64, c: f64) -> Result<Self, DistError> {
+        Self::with_distribution(k, DegreeDistribution::Robust { delta, c })
+    }
+    
+    /// Builds the table of `distribution` for `k` source blocks
+    pub fn with_distribution(k: usize, distribution: DegreeDistribution) -> Result<Self, DistError> {
+        distribution.validate(k)?;
+        Ok(Self::build(k, distribution))
+    }
+    
+    /// Returns the table of `distribution` for `k` source blocks, shared
+    /// with every other live user of the same parameters
+    ///
+    /// With `std`, tables are cached by `(k, distribution)` while anything
+    /// still holds them, so spinning up many decoders for one set of
+    /// parameters computes the CDF once. Without `std` a new table is built.
+    pub fn shared(k: usize, distribution: DegreeDistribution) -> Result<Arc<Self>, DistError> {
+        distribution.validate(k)?;
+        Ok(Self::build_shared(k, distribution))
+    }
+    
+    /// Like `shared`, without validating the distribution parameters
+    #[cfg(feature = "std")]
+    pub(crate) fn build_shared(k: usize, distribution: DegreeDistribution) -> Arc<Self> {
+        use std::sync::{Mutex, PoisonError, Weak};
+        
+        // Tables still in use somewhere, by parameters; entries are pruned
+        // once dropped
+        type Entry = (usize, DegreeDistribution, Weak<DistributionTable>);
+        static TABLES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+        
+        let mut tables = TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+        tables.retain(|(_, _, table)| table.strong_count() > 0);
+        let cached = tables
+            .iter()
+            .find(|entry| entry.0 == k && entry.1 == distribution)
+            .and_then(|(_, _, table)| table.upgrade());
+        cached.unwrap_or_else(|| {
+            let table = Arc::new(Self::build(k, distribution));
+            tables.push((k, distribution, Arc::downgrade(&table)));
+            table
+        })
+    }
+    
+    /// Like `shared`, without validating the distribution parameters
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn build_shared(k: usize, distribution: DegreeDistribution) -> Arc<Self> {
+        Arc::new(Self::build(k, distribution))
+    }
+    
+    /// Builds a table without validating the distribution parameters
+    pub(crate) fn build(k: usize, distribution: DegreeDistribution) -> Self {
+        Self {
+            k,
+            distribution,
+            cdf: distribution.cdf(k),
+        }
+    }
+    
+    /// Number of source blocks the table was built for
+    pub fn k(&self) -> usize {
+        self.k
+    }
+    
+    /// Samples a degree from the CDF using one draw from `source`, step 1
+    /// of `SAMPLING_VERSION`
+    fn sample_d(&self, source: &mut dyn RandomSource) -> usize {
+        let p = (source.next_u32() as f64 + 1.0) / (source.max_output() as f64 + 1.0);
+        
+        // A single source block can only be sent on its own; the draw above
+        // still advances the state so consecutive blocks get distinct seeds
+        if self.k <= 1 {
+            return 1;
+        }
+        
+        // The CDF never decreases, so a binary search finds the first entry
+        // above `p` in O(log k); degrees are 1-indexed, and with no such
+        // entry the degree falls back to the maximum
+        let ix = self.cdf.partition_point(|&v| v <= p);
+        (ix + 1).min(self.cdf.len())
+    }
+    
+    /// Samples a source index uniformly from 0..k
+    ///
+    /// Generator outputs lie in [0, max_output]. Taking them modulo `k` would
+    /// favour low indices whenever `k` does not divide the range, so draws at
+    /// or above the largest multiple of `k` are rejected and redrawn, step 2
+    /// of `SAMPLING_VERSION`.
+    fn sample_index(&self, source: &mut dyn RandomSource) -> usize {
+        let range = source.max_output() as u64 + 1;
+        let limit = range - range % self.k as u64;
+        loop {
+            let r = source.next_u32() as u64;
+            if r < limit {
+                return (r % self.k as u64) as usize;
+            }
+        }
+    }
+    
+    /// Samples a degree and that many distinct source indices
+    fn sample_src_blocks(&self, source: &mut dyn RandomSource) -> (usize, BTreeSet<usize>) {
+        let mut nums = BTreeSet::new();
+        let d = self.sample_src_blocks_into(source, &mut nums);
+        (d, nums)
+    }
+    
+    /// Like `sample_src_blocks`, but replaces the contents of `nums` instead
+    /// of allocating a new set
+    fn sample_src_blocks_into(&self, source: &mut dyn RandomSource, nums: &mut BTreeSet<usize>) -> usize {
+        let d = self.sample_d(source);
+        nums.clear();
+        while nums.len() < d {
+            nums.insert(self.sample_index(source));
+        }
+        d
+    }
+    
+    /// The distribution the table was built from
+    pub fn distribution(&self) -> DegreeDistribution {
+        self.distribution
+    }
+    
+    /// The CDF over degrees `1..=k`
+    pub fn cdf(&self) -> &[f64] {
+        &self.cdf
+    }
+}
+
 /// A Pseudorandom Number Generator that yields samples
 /// from the set of source blocks using the RSD degree
 /// distribution.
 pub struct PRNG {
-    state: Option<i64>,
+    source: Box<dyn RandomSource>,
     k: usize,
-    cdf: Vec<f64>,
+    table: Arc<DistributionTable>,
 }
 
 impl PRNG {
     /// Creates a new PRNG with the given parameters
-    pub fn new(k: usize, delta: f64, c: f64) -> Self {
-        let cdf = gen_rsd_cdf(k, delta, c);
+    ///
+    /// Fails if the parameters are rejected by `validate_params`.
+    pub fn new(k: usize, delta: f64, c: f64) -> Result<Self, DistError> {
+        Self::with_distribution(k, DegreeDistribution::Robust { delta, c })
+    }
+    
+    /// Creates a new PRNG sampling degrees from `distribution`
+    pub fn with_distribution(k: usize, distribution: DegreeDistribution) -> Result<Self, DistError> {
+        Self::with_source(k, distribution, Box::new(Minstd::default()))
+    }
+    
+    /// Creates a new PRNG drawing its random numbers from `source`
+    pub fn with_source(k: usize, distribution: DegreeDistribution, source: Box<dyn RandomSource>) -> Result<Self, DistError> {
+        distribution.validate(k)?;
+        Ok(Self::build(k, distribution, source))
+    }
+    
+    /// Creates a PRNG driven by a Lehmer LCG with the given constants
+    ///
+    /// Fails if the distribution or the LCG parameters are invalid.
+    pub fn with_lcg(k: usize, distribution: DegreeDistribution, params: LcgParams) -> Result<Self, DistError> {
+        Self::with_source(k, distribution, Box::new(Minstd::with_params(params)?))
+    }
+    
+    /// Creates a PRNG sampling from a shared, precomputed table
+    pub fn with_table(table: Arc<DistributionTable>) -> Self {
+        Self::with_table_and_source(table, Box::new(Minstd::default()))
+    }
+    
+    /// Creates a PRNG sampling from a shared table and drawing its random
+    /// numbers from `source`
+    pub fn with_table_and_source(table: Arc<DistributionTable>, source: Box<dyn RandomSource>) -> Self {
         Self {
-            state: None,
-            k,
-            cdf,
+            source,
+            k: table.k,
+            table,
         }
     }
     
+    /// Creates a PRNG without validating the distribution parameters
+    pub(crate) fn build(k: usize, distribution: DegreeDistribution, source: Box<dyn RandomSource>) -> Self {
+        Self::with_table_and_source(DistributionTable::build_shared(k, distribution), source)
+    }
+    
+    /// Rebuilds the degree distribution for `k` source blocks, keeping the
+    /// random source and its state
+    ///
+    /// A shared table is left untouched; the PRNG switches to the shared
+    /// table for the new `k`.
+    pub(crate) fn set_k(&mut self, k: usize) {
+        self.k = k;
+        self.table = DistributionTable::build_shared(k, self.table.distribution);
+    }
+    
     /// Creates a new PRNG with default parameters
     pub fn new_default(k: usize) -> Self {
-        Self::new(k, DEFAULT_DELTA, DEFAULT_C)
+        Self::build(k, DegreeDistribution::default(), Box::new(Minstd::default()))
     }
     
-    /// Executes the next iteration of the PRNG
-    /// evolution process, and returns the result
-    fn get_next(&mut self) -> i64 {
-        if let Some(current_state) = self.state {
-            self.state = Some(PRNG_A * current_state % PRNG_M);
-            self.state.unwrap()
-        } else {
-            panic!("PRNG state not initialized. Call set_seed first.")
-        }
+    /// Returns the random number generator behind this PRNG
+    pub fn source(&self) -> &dyn RandomSource {
+        self.source.as_ref()
     }
     
-    /// Samples degree given the precomputed
-    /// distributions and the linear PRNG output
-    fn sample_d(&mut self) -> usize {
-        let p = self.get_next() as f64 / PRNG_MAX_RAND as f64;
-        
-        for (ix, &v) in self.cdf.iter().enumerate() {
-            if v > p {
-                return ix + 1; // degrees are 1-indexed
-            }
-        }
-        
-        self.cdf.len() // fallback to max degree
+    /// Returns the degree distribution the CDF was built from
+    pub fn distribution(&self) -> DegreeDistribution {
+        self.table.distribution
+    }
+    
+    /// Returns the table this PRNG samples degrees from, for sharing
+    pub fn table(&self) -> &Arc<DistributionTable> {
+        &self.table
+    }
+    
+    /// Returns the precomputed degree distribution CDF
+    pub fn cdf(&self) -> &[f64] {
+        &self.table.cdf
     }
     
     /// Reset the state of the PRNG to the
     /// given seed
+    ///
+    /// The generator may normalize the seed, e.g. MINSTD reduces it to a
+    /// valid state and never keeps 0; `source().seed()` reports the result.
     pub fn set_seed(&mut self, seed: i64) {
-        self.state = Some(seed);
+        self.source.set_seed(seed);
     }
     
     /// Returns the indices of a set of `d` source blocks
     /// sampled from indices i = 0, ..., K-1 uniformly, where
     /// `d` is sampled from the RSD described above.
-    pub fn get_src_blocks(&mut self, seed: Option<i64>) -> (i64, usize, HashSet<usize>) {
+    ///
+    /// Panics if `seed` is `None` and no seed was ever set; `Encoder` checks
+    /// for this and returns `EncodeError::Unseeded` instead.
+    pub fn get_src_blocks(&mut self, seed: Option<i64>) -> (i64, usize, BTreeSet<usize>) {
         // Set seed if provided
         if let Some(s) = seed {
-            self.state = Some(s);
+            self.source.set_seed(s);
         }
         
         // Check if state is initialized
-        let blockseed = self.state.expect("PRNG state not initialized");
+        let blockseed = self.source.seed().expect("PRNG state not initialized");
         
-        // Sample degree
-        let d = self.sample_d();
-        
-        // Sample d unique blocks
-        let mut nums = HashSet::with_capacity(d);
-        while nums.len() < d {
-            let num = (self.get_next() % self.k as i64).abs() as usize;
-            nums.insert(num);
-        }
+        // Sample degree, then d unique blocks
+        let (d, nums) = self.table.sample_src_blocks(self.source.as_mut());
         
         (blockseed, d, nums)
     }
+    
+    /// Like `get_src_blocks`, but writes the indices into `indices`, reusing
+    /// its allocation, and returns the seed and degree
+    pub fn get_src_blocks_into(&mut self, seed: Option<i64>, indices: &mut BTreeSet<usize>) -> (i64, usize) {
+        if let Some(s) = seed {
+            self.source.set_seed(s);
+        }
+        let blockseed = self.source.seed().expect("PRNG state not initialized");
+        let d = self.table.sample_src_blocks_into(self.source.as_mut(), indices);
+        (blockseed, d)
+    }
+    
+    /// Returns the degree and source indices a block with `seed` combines,
+    /// without touching this PRNG's state
+    ///
+    /// Gives the same result as `get_src_blocks(Some(seed))` but runs on a
+    /// copy of the generator.
+    pub fn src_blocks_for_seed(&self, seed: i64) -> (usize, BTreeSet<usize>) {
+        let mut source = self.source.clone_box();
+        source.set_seed(seed);
+        self.table.sample_src_blocks(source.as_mut())
+    }
 }
 
 #[cfg(test)]
@@ -167,27 +816,311 @@ mod tests {
     
     #[test]
     fn test_prng_initialization() {
-        let prng = PRNG::new(100, 0.5, 0.1);
+        let prng = PRNG::new(100, 0.5, 0.1).unwrap();
         assert_eq!(prng.k, 100);
-        assert_eq!(prng.cdf.len(), 100);
+        assert_eq!(prng.cdf().len(), 100);
     }
     
     #[test]
     fn test_prng_with_seed() {
-        let mut prng = PRNG::new(100, 0.5, 0.1);
+        let mut prng = PRNG::new(100, 0.5, 0.1).unwrap();
         prng.set_seed(42);
         let (blockseed, d, blocks) = prng.get_src_blocks(None);
         assert_eq!(blockseed, 42);
-        assert!(d >= 1 && d <= 100);
+        assert!((1..=100).contains(&d));
         assert_eq!(blocks.len(), d);
     }
     
     #[test]
     fn test_prng_with_direct_seed() {
-        let mut prng = PRNG::new(100, 0.5, 0.1);
+        let mut prng = PRNG::new(100, 0.5, 0.1).unwrap();
         let (blockseed, d, blocks) = prng.get_src_blocks(Some(42));
         assert_eq!(blockseed, 42);
-        assert!(d >= 1 && d <= 100);
+        assert!((1..=100).contains(&d));
         assert_eq!(blocks.len(), d);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_sampling_version_vectors() {
+        // Neighbours under `SAMPLING_VERSION` 1; if these change, bump it
+        assert_eq!(SAMPLING_VERSION, 1);
+        let expected: [(i64, &[usize]); 3] = [
+            (1, &[6]),
+            (12345, &[6, 13]),
+            (2_000_000_000, &[11, 13, 16, 22, 26, 28]),
+        ];
+        let mut prng = PRNG::new_default(37);
+        for (seed, neighbours) in expected {
+            let (_, d, indices) = prng.get_src_blocks(Some(seed));
+            let mut indices: Vec<usize> = indices.into_iter().collect();
+            indices.sort_unstable();
+            assert_eq!(d, neighbours.len());
+            assert_eq!(indices, neighbours);
+        }
+    }
+    
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_shared_tables() {
+        // An unusual k keeps other tests from holding the same table
+        let k = 4321;
+        let first = PRNG::new_default(k);
+        let second = PRNG::with_source(k, DegreeDistribution::default(), Box::new(SplitMix::default())).unwrap();
+        assert!(Arc::ptr_eq(first.table(), second.table()));
+        assert!(Arc::ptr_eq(first.table(), &DistributionTable::shared(k, DegreeDistribution::default()).unwrap()));
+        assert!(!Arc::ptr_eq(first.table(), PRNG::new(k, 0.1, DEFAULT_C).unwrap().table()));
+        assert!(!Arc::ptr_eq(first.table(), PRNG::new_default(k + 1).table()));
+        
+        // Once every user is gone the table is dropped, not kept forever
+        let weak = Arc::downgrade(first.table());
+        drop((first, second));
+        assert!(weak.upgrade().is_none());
+        assert_eq!(DistributionTable::shared(0, DegreeDistribution::Ideal).err(), Some(DistError::NoSourceBlocks));
+    }
+    
+    #[test]
+    fn test_sample_d_matches_linear_scan() {
+        let linear = |cdf: &[f64], p: f64| cdf.iter().position(|&v| v > p).map_or(cdf.len(), |ix| ix + 1);
+        for (k, distribution) in [(1000, DegreeDistribution::default()), (257, DegreeDistribution::Ideal), (40, DegreeDistribution::Dense)] {
+            let table = DistributionTable::build(k, distribution);
+            let mut source = Minstd::default();
+            source.set_seed(99);
+            let mut probe = source.clone();
+            for _ in 0..5000 {
+                let r = probe.next_u32();
+                let p = (r as f64 + 1.0) / (probe.max_output() as f64 + 1.0);
+                assert_eq!(table.sample_d(&mut source), linear(table.cdf(), p));
+            }
+        }
+    }
+    
+    #[test]
+    fn test_zero_seed_remapped() {
+        let mut source = Minstd::default();
+        for (seed, state) in [(0, DEFAULT_SEED), (PRNG_M, DEFAULT_SEED), (-3, PRNG_M - 3), (PRNG_M + 5, 5), (7, 7)] {
+            source.set_seed(seed);
+            assert_eq!(source.seed(), Some(state));
+        }
+        
+        // Without the remap state 0 would return 0 forever
+        source.set_seed(0);
+        let outputs: BTreeSet<u32> = (0..10).map(|_| source.next_u32()).collect();
+        assert_eq!(outputs.len(), 10);
+        
+        assert!(!source.is_valid_seed(0));
+        assert!(!source.is_valid_seed(PRNG_M));
+        assert!(!source.is_valid_seed(-1));
+        assert!(source.is_valid_seed(PRNG_M - 1));
+        assert!(SplitMix::default().is_valid_seed(0));
+        assert!(!SplitMix::default().is_valid_seed(-1));
+    }
+    
+    #[test]
+    fn test_custom_lcg_params() {
+        // C++ std::minstd_rand: a = 48271, m = 2^31 - 1, seeded with 1
+        let params = LcgParams { a: 48271, m: (1 << 31) - 1 };
+        let mut source = Minstd::with_params(params).unwrap();
+        source.set_seed(1);
+        let states: Vec<i64> = (0..5)
+            .map(|_| {
+                source.next_u32();
+                source.seed().unwrap()
+            })
+            .collect();
+        assert_eq!(states, vec![48271, 182605794, 1291394886, 1914720637, 2078669041]);
+        for _ in 5..10_000 {
+            source.next_u32();
+        }
+        assert_eq!(source.seed(), Some(399268537));
+        assert_eq!(source.name(), "lcg");
+        
+        // PRNG draws follow the custom generator
+        let mut prng = PRNG::with_lcg(10, DegreeDistribution::default(), params).unwrap();
+        let (seed, ..) = prng.get_src_blocks(Some(1));
+        assert_eq!(seed, 1);
+        assert_ne!(prng.source().seed(), Some(1));
+        
+        assert_eq!(
+            PRNG::with_lcg(10, DegreeDistribution::default(), LcgParams { a: 5, m: 5 }).err(),
+            Some(DistError::InvalidLcgParams { a: 5, m: 5 })
+        );
+        assert_eq!(Minstd::default().params(), LcgParams::MINSTD);
+    }
+    
+    #[test]
+    fn test_src_blocks_for_seed() {
+        let mut prng = PRNG::new_default(50);
+        prng.set_seed(7);
+        
+        let first = prng.src_blocks_for_seed(42);
+        assert_eq!(prng.src_blocks_for_seed(42), first);
+        assert_eq!(prng.source().seed(), Some(7));
+        
+        let (seed, d, indices) = prng.get_src_blocks(Some(42));
+        assert_eq!((seed, (d, indices)), (42, first));
+    }
+    
+    #[test]
+    fn test_shared_table() {
+        use crate::luby_transform::decoder::Decoder;
+        use crate::luby_transform::encoder::Encoder;
+        use crate::luby_transform::error::EncodeError;
+        
+        let k = 64;
+        let table = Arc::new(DistributionTable::new(k, DEFAULT_DELTA, DEFAULT_C).unwrap());
+        let mut first = PRNG::with_table(table.clone());
+        let mut second = PRNG::with_table(table.clone());
+        first.set_seed(99);
+        second.set_seed(99);
+        let degrees: Vec<usize> = (0..200).map(|_| first.get_src_blocks(None).1).collect();
+        assert_eq!(degrees, (0..200).map(|_| second.get_src_blocks(None).1).collect::<Vec<_>>());
+        assert_eq!(first.cdf(), PRNG::new(k, DEFAULT_DELTA, DEFAULT_C).unwrap().cdf());
+        
+        // Encoder and decoder share the one CDF and still agree
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i]).collect();
+        let mut encoder = Encoder::with_table(source_blocks.clone(), table.clone()).unwrap();
+        encoder.reset_seed(5);
+        let mut decoder = Decoder::with_table(table.clone(), 1);
+        assert_eq!(Arc::strong_count(&table), 5);
+        for (seed, d, _, data) in encoder.iter().take(10 * k) {
+            decoder.add_encoded_block(seed, d, data);
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert_eq!(decoder.get_all_decoded_blocks(), Some(source_blocks));
+        assert_eq!(
+            Encoder::with_table(vec![vec![0]], table).err(),
+            Some(EncodeError::TableMismatch { table_k: k, k: 1 })
+        );
+    }
+    
+    #[test]
+    fn test_sample_index_uniform() {
+        let k = 7;
+        let draws = 70_000;
+        let mut prng = PRNG::new_default(k);
+        prng.set_seed(12345);
+        
+        let mut counts = vec![0usize; k];
+        for _ in 0..draws {
+            counts[prng.table.sample_index(prng.source.as_mut())] += 1;
+        }
+        
+        // Each index should land within 5% of its expected share
+        let expected = draws / k;
+        for &count in &counts {
+            assert!(count.abs_diff(expected) < expected / 20, "counts = {:?}", counts);
+        }
+    }
+    
+    #[test]
+    fn test_ideal_and_robust_tails() {
+        let k = 100;
+        let ideal = PRNG::with_distribution(k, DegreeDistribution::Ideal).unwrap();
+        let robust = PRNG::with_distribution(k, DegreeDistribution::default()).unwrap();
+        assert!((ideal.cdf()[k - 1] - 1.0).abs() < 1e-9);
+        assert!((robust.cdf()[k - 1] - 1.0).abs() < 1e-9);
+        
+        // Ideal Soliton: P(degree > d) = 1/d - 1/k
+        for d in [2, 10, 50] {
+            let tail = 1.0 - ideal.cdf()[d - 1];
+            assert!((tail - (1.0 / d as f64 - 1.0 / k as f64)).abs() < 1e-9);
+        }
+        
+        // Past the robust spike only the rescaled rho remains, so the
+        // robust tail is strictly lighter than the ideal one
+        for d in 50..k - 1 {
+            assert!(1.0 - robust.cdf()[d - 1] < 1.0 - ideal.cdf()[d - 1]);
+        }
+    }
+    
+    #[test]
+    fn test_dense_cdf() {
+        // C(4, d) / 15 for d = 1..=4
+        let expected = [4.0 / 15.0, 10.0 / 15.0, 14.0 / 15.0, 1.0];
+        for (value, expected) in gen_dense_cdf(4).iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert_eq!(gen_dense_cdf(1), [1.0]);
+        assert!(gen_dense_cdf(0).is_empty());
+        
+        // Large k only loses the far tails
+        let cdf = gen_dense_cdf(5000);
+        assert!((cdf[4999] - 1.0).abs() < 1e-9);
+        assert!(cdf[2399] < 0.01 && cdf[2599] > 0.99);
+        assert!(DegreeDistribution::Dense.validate(0).is_err());
+        assert_eq!(DegreeDistribution::Dense.validate(DENSE_MAX_K), Ok(()));
+        assert_eq!(
+            DegreeDistribution::Dense.validate(DENSE_MAX_K + 1),
+            Err(DistError::TooManyBlocks { k: DENSE_MAX_K + 1, max: DENSE_MAX_K })
+        );
+    }
+    
+    #[test]
+    fn test_seed_sequence_streams() {
+        let seeds = SeedSequence::new(12345);
+        let starts: Vec<i64> = (0..STREAM_COUNT).map(|id| seeds.stream(id).unwrap()).collect();
+        assert_eq!(seeds.stream(STREAM_COUNT), None);
+        assert_eq!(starts.iter().collect::<BTreeSet<_>>().len(), starts.len());
+        
+        // Streams are evenly spaced and the last one runs into stream 0
+        let jump = pow_mod(PRNG_A, STREAM_STRIDE);
+        for (id, start) in starts.iter().enumerate() {
+            assert_eq!(start * jump % PRNG_M, starts[(id + 1) % starts.len()]);
+        }
+        
+        // Indexed seeds are an even number of steps from the root, i.e.
+        // quadratic residues relative to it, and stream starts never are
+        let residue = |seed: i64| pow_mod(seed, (PRNG_M as u64 - 1) / 2) == 1;
+        let root_residue = residue(seeds.root());
+        assert!((0..1000).all(|i| residue(seeds.nth(i)) == root_residue));
+        assert!(starts.iter().all(|&start| residue(start) != root_residue));
+    }
+    
+    #[test]
+    fn test_validate_params() {
+        for (k, delta, c) in [(1, 0.5, 0.1), (10, 0.01, 0.03), (1000, 0.5, 0.1), (50, 0.99, 2.0)] {
+            assert_eq!(validate_params(k, delta, c), Ok(()));
+            let total: f64 = gen_mu(k, delta, c).iter().sum();
+            assert!((total - 1.0).abs() < 1e-9, "sum = {}", total);
+        }
+        
+        assert_eq!(validate_params(0, 0.5, 0.1), Err(DistError::NoSourceBlocks));
+        for delta in [0.0, -0.5, 1.0, 2.0, f64::NAN] {
+            assert!(matches!(validate_params(10, delta, 0.1), Err(DistError::DeltaOutOfRange(_))));
+        }
+        for c in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(validate_params(10, 0.5, c), Err(DistError::NonPositiveC(_))));
+        }
+        
+        let err = PRNG::new(10, 1.5, 0.1).err().unwrap();
+        assert_eq!(err.to_string(), "delta must lie strictly between 0 and 1, got 1.5");
+        assert!(PRNG::with_distribution(0, DegreeDistribution::Ideal).is_err());
+    }
+    
+    #[test]
+    fn test_small_k_distributions() {
+        for k in 1..=3 {
+            let s = DEFAULT_C * compat::ln(k as f64 / DEFAULT_DELTA) * compat::sqrt(k as f64);
+            let tau = gen_tau(s, k, DEFAULT_DELTA);
+            assert_eq!(tau.len(), k);
+            assert!(tau.iter().all(|&t| t >= 0.0), "k = {}, tau = {:?}", k, tau);
+            
+            let mu = gen_mu(k, DEFAULT_DELTA, DEFAULT_C);
+            assert_eq!(mu.len(), k);
+            assert!(mu.iter().all(|&p| p >= 0.0));
+            assert!((mu.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+        
+        // A single source block always yields degree 1
+        assert_eq!(gen_tau(1.0, 1, 0.5), vec![0.0]);
+        let mut prng = PRNG::new_default(1);
+        for seed in 1..20 {
+            let (_, d, blocks) = prng.get_src_blocks(Some(seed));
+            assert_eq!((d, blocks.len()), (1, 1));
+        }
+        
+        assert!(gen_tau(1.0, 0, 0.5).is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+use super::error::LtError;
+use super::prng::DegreeDistribution;
+use super::wire::{self, Reader};
+#[cfg(not(feature = "std"))]
+use super::compat::{String, ToString, Vec};
+
+// Leading bytes and version of the format written by `DecoderState::to_bytes`
+const SNAPSHOT_MAGIC: &[u8; 4] = b"LTDS";
+const SNAPSHOT_VERSION: u8 = 11;
+
+/// A received block that has not been fully used yet, as captured in a
+/// `DecoderState`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockState {
+    /// Index the decoder assigned to the block
+    pub index: usize,
+    /// Seed the block was generated from; 0 for precode constraints
+    pub seed: i64,
+    /// Degree the block was received with
+    pub degree: usize,
+    /// Intermediate block indices the block combines, sorted
+    pub dependencies: Vec<usize>,
+    /// Block data
+    pub data: Vec<i32>,
+    /// Whether the block took part in a Gaussian elimination that decoded blocks
+    pub eliminated: bool,
+    /// Whether the block is a precode parity relation rather than a
+    /// received block
+    pub constraint: bool,
+}
+
+/// Plain-data snapshot of an in-progress decode, from `Decoder::snapshot`
+///
+/// Pass it to `Decoder::restore` to resume decoding, or persist it with
+/// `to_bytes`, or with any serde format under the `serde` feature. Blocks
+/// are kept sorted by index so equal decoders give equal states.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecoderState {
+    /// Number of source blocks
+    pub k: usize,
+    /// Size of each block
+    pub block_size: usize,
+    /// Unpadded size of the final source block, if known
+    pub last_block_size: Option<usize>,
+    /// Degree distribution shared with the encoder
+    pub distribution: DegreeDistribution,
+    /// Name of the random source, see `random_source_by_name`
+    pub random_source: String,
+    /// Number of precode parity blocks, 0 without a precode
+    pub parity_count: usize,
+    /// Received blocks still held by the decoder
+    pub received_blocks: Vec<BlockState>,
+    /// Decoded intermediate blocks as (index, data), sorted by index
+    pub decoded_blocks: Vec<(usize, Vec<i32>)>,
+    /// Decoding round counter
+    pub current_round: usize,
+    /// Index the decoder assigns to the next received block
+    pub next_block_index: usize,
+    /// Blocks dropped because they carried no new information
+    pub blocks_wasted: usize,
+    /// Blocks passed to `add_encoded_block`, duplicates included
+    pub blocks_received: usize,
+    /// Blocks rejected by a CRC, degree or length check
+    pub blocks_corrupted: usize,
+    /// Blocks dropped to stay within the buffered packet limit
+    pub blocks_evicted: usize,
+    /// Expected SHA-256 digest of the source blocks, if set
+    pub source_digest: Option<[u8; 32]>,
+    /// Seeds of every block accepted so far, sorted
+    pub seen_seeds: Vec<i64>,
+}
+
+impl DecoderState {
+    /// Serializes the state into a compact little-endian byte format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SNAPSHOT_MAGIC);
+        out.push(SNAPSHOT_VERSION);
+        wire::put_u64(&mut out, self.k as u64);
+        wire::put_u64(&mut out, self.block_size as u64);
+        // Stored as `size + 1`, with 0 meaning unknown
+        wire::put_u64(&mut out, self.last_block_size.map_or(0, |len| len as u64 + 1));
+        // Distribution tag, then its parameters (zero for Ideal and Dense)
+        let (tag, delta, c) = match self.distribution {
+            DegreeDistribution::Ideal => (0, 0.0, 0.0),
+            DegreeDistribution::Robust { delta, c } => (1, delta, c),
+            DegreeDistribution::Dense => (2, 0.0, 0.0),
+        };
+        out.push(tag);
+        wire::put_f64(&mut out, delta);
+        wire::put_f64(&mut out, c);
+        wire::put_u64(&mut out, self.random_source.len() as u64);
+        out.extend_from_slice(self.random_source.as_bytes());
+        wire::put_u64(&mut out, self.parity_count as u64);
+        wire::put_u64(&mut out, self.current_round as u64);
+        wire::put_u64(&mut out, self.next_block_index as u64);
+        wire::put_u64(&mut out, self.blocks_wasted as u64);
+        wire::put_u64(&mut out, self.blocks_received as u64);
+        wire::put_u64(&mut out, self.blocks_corrupted as u64);
+        wire::put_u64(&mut out, self.blocks_evicted as u64);
+        // Presence flag, then the digest itself
+        match &self.source_digest {
+            Some(digest) => {
+                out.push(1);
+                out.extend_from_slice(digest);
+            }
+            None => out.push(0),
+        }
+        
+        wire::put_u64(&mut out, self.received_blocks.len() as u64);
+        for block in &self.received_blocks {
+            wire::put_u64(&mut out, block.index as u64);
+            wire::put_i64(&mut out, block.seed);
+            wire::put_u64(&mut out, block.degree as u64);
+            wire::put_u64(&mut out, block.dependencies.len() as u64);
+            for &dep in &block.dependencies {
+                wire::put_u64(&mut out, dep as u64);
+            }
+            wire::put_u64(&mut out, block.data.len() as u64);
+            wire::put_i32s(&mut out, &block.data);
+            // Flags: bit 0 eliminated, bit 1 precode constraint
+            out.push(block.eliminated as u8 | (block.constraint as u8) << 1);
+        }
+        
+        wire::put_u64(&mut out, self.decoded_blocks.len() as u64);
+        for (idx, data) in &self.decoded_blocks {
+            wire::put_u64(&mut out, *idx as u64);
+            wire::put_u64(&mut out, data.len() as u64);
+            wire::put_i32s(&mut out, data);
+        }
+        
+        wire::put_u64(&mut out, self.seen_seeds.len() as u64);
+        for &seed in &self.seen_seeds {
+            wire::put_i64(&mut out, seed);
+        }
+        
+        out
+    }
+    
+    /// Parses bytes produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<DecoderState, LtError> {
+        let truncated = LtError::InvalidSnapshot("truncated");
+        let mut reader = Reader::new(bytes);
+        
+        if reader.bytes(4) != Some(&SNAPSHOT_MAGIC[..]) {
+            return Err(LtError::InvalidSnapshot("bad magic"));
+        }
+        if reader.bytes(1) != Some(&[SNAPSHOT_VERSION][..]) {
+            return Err(LtError::InvalidSnapshot("unsupported version"));
+        }
+        
+        let k = reader.usize().ok_or(truncated.clone())?;
+        let block_size = reader.usize().ok_or(truncated.clone())?;
+        let last_block_size = reader.usize().ok_or(truncated.clone())?.checked_sub(1);
+        let tag = reader.bytes(1).ok_or(truncated.clone())?[0];
+        let delta = reader.f64().ok_or(truncated.clone())?;
+        let c = reader.f64().ok_or(truncated.clone())?;
+        let distribution = match tag {
+            0 => DegreeDistribution::Ideal,
+            1 => DegreeDistribution::Robust { delta, c },
+            2 => DegreeDistribution::Dense,
+            _ => return Err(LtError::InvalidSnapshot("unknown degree distribution")),
+        };
+        let name_len = reader.usize().ok_or(truncated.clone())?;
+        let name = reader.bytes(name_len).ok_or(truncated.clone())?;
+        let random_source = core::str::from_utf8(name)
+            .map_err(|_| LtError::InvalidSnapshot("unknown random source"))?
+            .to_string();
+        let parity_count = reader.usize().ok_or(truncated.clone())?;
+        let current_round = reader.usize().ok_or(truncated.clone())?;
+        let next_block_index = reader.usize().ok_or(truncated.clone())?;
+        let blocks_wasted = reader.usize().ok_or(truncated.clone())?;
+        let blocks_received = reader.usize().ok_or(truncated.clone())?;
+        let blocks_corrupted = reader.usize().ok_or(truncated.clone())?;
+        let blocks_evicted = reader.usize().ok_or(truncated.clone())?;
+        let source_digest = match reader.bytes(1).ok_or(truncated.clone())?[0] {
+            0 => None,
+            _ => Some(reader.bytes(32).ok_or(truncated.clone())?.try_into().unwrap()),
+        };
+        
+        let received_count = reader.usize().ok_or(truncated.clone())?;
+        let mut received_blocks = Vec::new();
+        for _ in 0..received_count {
+            let index = reader.usize().ok_or(truncated.clone())?;
+            let seed = reader.i64().ok_or(truncated.clone())?;
+            let degree = reader.usize().ok_or(truncated.clone())?;
+            let dependency_count = reader.usize().ok_or(truncated.clone())?;
+            let mut dependencies = Vec::new();
+            for _ in 0..dependency_count {
+                dependencies.push(reader.usize().ok_or(truncated.clone())?);
+            }
+            let len = reader.usize().ok_or(truncated.clone())?;
+            let data = reader.i32s(len).ok_or(truncated.clone())?;
+            let flags = reader.bytes(1).ok_or(truncated.clone())?[0];
+            let (eliminated, constraint) = (flags & 1 != 0, flags & 2 != 0);
+            received_blocks.push(BlockState { index, seed, degree, dependencies, data, eliminated, constraint });
+        }
+        
+        let decoded_count = reader.usize().ok_or(truncated.clone())?;
+        let mut decoded_blocks = Vec::new();
+        for _ in 0..decoded_count {
+            let idx = reader.usize().ok_or(truncated.clone())?;
+            let len = reader.usize().ok_or(truncated.clone())?;
+            decoded_blocks.push((idx, reader.i32s(len).ok_or(truncated.clone())?));
+        }
+        
+        let seen_count = reader.usize().ok_or(truncated.clone())?;
+        let mut seen_seeds = Vec::new();
+        for _ in 0..seen_count {
+            seen_seeds.push(reader.i64().ok_or(truncated.clone())?);
+        }
+        
+        if !reader.is_empty() {
+            return Err(LtError::InvalidSnapshot("trailing bytes"));
+        }
+        
+        Ok(DecoderState {
+            k,
+            block_size,
+            last_block_size,
+            distribution,
+            random_source,
+            parity_count,
+            received_blocks,
+            decoded_blocks,
+            current_round,
+            next_block_index,
+            blocks_wasted,
+            blocks_received,
+            blocks_corrupted,
+            blocks_evicted,
+            source_digest,
+            seen_seeds,
+        })
+    }
+}
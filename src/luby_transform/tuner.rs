@@ -0,0 +1,173 @@
+use super::decoder::Decoder;
+use super::error::DistError;
+use super::prng::{validate_params, DegreeDistribution, DistributionTable};
+use alloc::sync::Arc;
+
+// Observations averaged before each hill-climbing decision
+const BATCH: usize = 8;
+// Initial relative step applied to a parameter
+const INITIAL_STEP: f64 = 0.25;
+// Tuning stops once the step falls below this
+const MIN_STEP: f64 = 1.0 / 64.0;
+
+/// Which RSD parameter the tuner is currently adjusting
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Param {
+    C,
+    Delta,
+}
+
+/// Hill-climbing tuner for the Robust Soliton parameters of a fixed `k`
+///
+/// Feed it the decode overhead (blocks received divided by `k`) of each
+/// completed transfer encoded with `recommended_params`. After every batch
+/// of observations it compares the mean overhead with the best batch so far:
+/// a step that did not make things worse is kept and repeated, a worse one
+/// is undone and the opposite direction tried. Once both directions of a
+/// parameter fail, the tuner switches between `c` and `delta` and halves the
+/// step. Steps start in the cheaper direction, lowering `c` and raising
+/// `delta`, which shrinks the spike of extra low-degree blocks.
+///
+/// Encoders and decoders must agree on the parameters, so switch both at
+/// once, e.g. through a shared `table`.
+#[derive(Clone, Debug)]
+pub struct Tuner {
+    k: usize,
+    params: (f64, f64),               // (delta, c) currently being tried
+    best: Option<(f64, (f64, f64))>,  // Mean overhead and (delta, c) of the best batch
+    sum: f64,                         // Overhead observed in the current batch
+    count: usize,
+    param: Param,
+    cheaper: bool,                    // Direction of the next step
+    reversed: bool,                   // Whether the current parameter already reversed
+    step: f64,
+}
+
+impl Tuner {
+    /// Creates a tuner for `k` source blocks starting from `delta` and `c`
+    ///
+    /// Fails if the parameters are rejected by `validate_params`.
+    pub fn new(k: usize, delta: f64, c: f64) -> Result<Self, DistError> {
+        validate_params(k, delta, c)?;
+        Ok(Self {
+            k,
+            params: (delta, c),
+            best: None,
+            sum: 0.0,
+            count: 0,
+            param: Param::C,
+            cheaper: true,
+            reversed: false,
+            step: INITIAL_STEP,
+        })
+    }
+
+    /// Records the overhead ratio (blocks received / `k`) of one decode
+    /// that used `recommended_params`
+    ///
+    /// Non-finite or non-positive ratios are ignored.
+    pub fn observe(&mut self, overhead_ratio: f64) {
+        if !(overhead_ratio > 0.0 && overhead_ratio.is_finite()) {
+            return;
+        }
+        self.sum += overhead_ratio;
+        self.count += 1;
+        if self.count < BATCH {
+            return;
+        }
+
+        let mean = self.sum / self.count as f64;
+        self.sum = 0.0;
+        self.count = 0;
+
+        match self.best {
+            Some((best_mean, best_params)) if mean > best_mean => {
+                // Undo the step and try the other way, or the other parameter
+                self.params = best_params;
+                if self.reversed {
+                    self.param = match self.param {
+                        Param::C => Param::Delta,
+                        Param::Delta => Param::C,
+                    };
+                    self.cheaper = true;
+                    self.reversed = false;
+                    self.step /= 2.0;
+                } else {
+                    self.cheaper = !self.cheaper;
+                    self.reversed = true;
+                }
+            }
+            _ => self.best = Some((mean, self.params)),
+        }
+
+        if self.step >= MIN_STEP {
+            self.params = self.nudge(self.params);
+        }
+    }
+
+    /// Records the overhead of a decoder that has finished
+    ///
+    /// Incomplete decoders are ignored.
+    pub fn observe_decoder(&mut self, decoder: &Decoder) {
+        if decoder.is_complete() && self.k > 0 {
+            self.observe(decoder.blocks_received() as f64 / self.k as f64);
+        }
+    }
+
+    /// Returns the `(delta, c)` to encode and decode the next transfers with
+    pub fn recommended_params(&self) -> (f64, f64) {
+        self.params
+    }
+
+    /// Returns the lowest mean overhead observed over a batch so far, with
+    /// the `(delta, c)` that produced it
+    pub fn best(&self) -> Option<(f64, (f64, f64))> {
+        self.best
+    }
+
+    /// Builds a degree table for the recommended parameters, to share
+    /// between a new `Encoder` and `Decoder`
+    pub fn table(&self) -> Arc<DistributionTable> {
+        let (delta, c) = self.params;
+        Arc::new(DistributionTable::build(self.k, DegreeDistribution::Robust { delta, c }))
+    }
+
+    /// Applies one step to the current parameter, keeping both in range
+    fn nudge(&self, (delta, c): (f64, f64)) -> (f64, f64) {
+        let factor = if self.cheaper { 1.0 + self.step } else { 1.0 - self.step };
+        match self.param {
+            Param::C => (delta, (c / factor).max(1e-3)),
+            Param::Delta => ((delta * factor).clamp(1e-6, 0.99), c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::luby_transform::prng::{DEFAULT_C, DEFAULT_DELTA};
+
+    #[test]
+    fn test_tuner_direction() {
+        // Flat high overhead: lowering c never hurts, so it keeps falling
+        let mut tuner = Tuner::new(100, DEFAULT_DELTA, DEFAULT_C).unwrap();
+        for _ in 0..3 * BATCH {
+            tuner.observe(1.8);
+        }
+        let (delta, c) = tuner.recommended_params();
+        assert_eq!(delta, DEFAULT_DELTA);
+        assert!(c < DEFAULT_C, "{c}");
+        assert_eq!(tuner.table().distribution(), DegreeDistribution::Robust { delta, c });
+
+        // Overhead that grows as c shrinks sends the tuner back up
+        let mut tuner = Tuner::new(100, DEFAULT_DELTA, DEFAULT_C).unwrap();
+        for _ in 0..4 * BATCH {
+            let (_, c) = tuner.recommended_params();
+            tuner.observe(1.2 + DEFAULT_C / c);
+        }
+        assert!(tuner.recommended_params().1 > DEFAULT_C);
+        assert_eq!(tuner.best().map(|(_, params)| params.1 > DEFAULT_C), Some(true));
+
+        assert!(Tuner::new(100, 1.5, DEFAULT_C).is_err());
+    }
+}
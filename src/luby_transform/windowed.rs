@@ -0,0 +1,141 @@
+use super::decoder::Decoder;
+use super::error::ConfigError;
+use super::prng::{DegreeDistribution, DistributionTable};
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use super::compat::Vec;
+
+/// Decoder for large objects split into independently coded windows
+///
+/// The `k` source blocks are partitioned into consecutive windows of
+/// `window_size` blocks (the last one may be shorter), each encoded by its
+/// own `Encoder` with the default distribution, as in the source block
+/// partitioning of RFC 5053. Encoded blocks carry their window id next to
+/// the seed and are routed to that window's decoder.
+///
+/// Windows complete independently. `flush_completed_windows` hands out their
+/// data and frees the decoder, so only windows still in progress are held
+/// in memory.
+pub struct WindowedDecoder {
+    window_size: usize,
+    windows: Vec<Option<Decoder>>, // `None` once the window has been flushed
+}
+
+impl WindowedDecoder {
+    /// Creates a decoder for `k` source blocks of `block_size` symbols, split
+    /// into windows of `window_size` blocks
+    ///
+    /// Fails if `window_size` is 0.
+    pub fn new(k: usize, window_size: usize, block_size: usize) -> Result<Self, ConfigError> {
+        if window_size == 0 {
+            return Err(ConfigError::ZeroWindowSize);
+        }
+
+        // Full windows share one degree table
+        let full = Arc::new(DistributionTable::build(window_size, DegreeDistribution::default()));
+        let windows = (0..k.div_ceil(window_size))
+            .map(|window| {
+                let window_k = window_size.min(k - window * window_size);
+                let table = if window_k == window_size {
+                    Arc::clone(&full)
+                } else {
+                    Arc::new(DistributionTable::build(window_k, DegreeDistribution::default()))
+                };
+                Some(Decoder::with_table(table, block_size))
+            })
+            .collect();
+
+        Ok(Self { window_size, windows })
+    }
+
+    /// Number of windows
+    pub fn window_count(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// Number of source blocks in each window but possibly the last
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Adds an encoded block to `window`, see `Decoder::add_encoded_block`
+    ///
+    /// Blocks for unknown or already flushed windows are dropped and `None`
+    /// is returned.
+    pub fn add_encoded_block(&mut self, window: usize, seed: i64, degree: usize, data: Vec<i32>) -> Option<usize> {
+        let decoder = self.windows.get_mut(window)?.as_mut()?;
+        decoder.add_encoded_block(seed, degree, data)
+    }
+
+    /// Returns the decoder of a window that has not been flushed yet
+    pub fn window(&self, window: usize) -> Option<&Decoder> {
+        self.windows.get(window)?.as_ref()
+    }
+
+    /// Takes the data of every window that finished decoding since the last
+    /// call, as (window id, source blocks) in window order
+    ///
+    /// Flushed windows release their decoder and ignore further blocks.
+    pub fn flush_completed_windows(&mut self) -> Vec<(usize, Vec<Vec<i32>>)> {
+        let mut flushed = Vec::new();
+        for (window, slot) in self.windows.iter_mut().enumerate() {
+            if slot.as_ref().is_some_and(Decoder::is_complete)
+                && let Some(blocks) = slot.take().and_then(|decoder| decoder.get_all_decoded_blocks())
+            {
+                flushed.push((window, blocks));
+            }
+        }
+        flushed
+    }
+
+    /// Checks whether every window has finished decoding
+    pub fn is_complete(&self) -> bool {
+        self.windows.iter().flatten().all(Decoder::is_complete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::luby_transform::encoder::Encoder;
+
+    #[test]
+    fn test_windowed_round_trip() {
+        let (k, window_size) = (45, 16);
+        let source_blocks: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * 7, !i]).collect();
+        let mut encoders: Vec<Encoder> = source_blocks
+            .chunks(window_size)
+            .enumerate()
+            .map(|(window, chunk)| Encoder::new_default(chunk.to_vec(), Some(window as i64 + 1)))
+            .collect();
+
+        let mut decoder = WindowedDecoder::new(k, window_size, 3).unwrap();
+        assert_eq!(decoder.window_count(), 3);
+        assert_eq!(decoder.window(2).map(|last| last.missing_indices().len()), Some(13));
+
+        // Interleave blocks across windows and flush as windows finish
+        let mut recovered = vec![None; decoder.window_count()];
+        while !decoder.is_complete() {
+            for (window, encoder) in encoders.iter_mut().enumerate() {
+                let (seed, degree, _, data) = encoder.generate_encoded_block(None).unwrap();
+                decoder.add_encoded_block(window, seed, degree, data);
+            }
+            for (window, blocks) in decoder.flush_completed_windows() {
+                assert!(recovered[window].replace(blocks).is_none());
+                assert!(decoder.window(window).is_none());
+            }
+        }
+        for (window, blocks) in decoder.flush_completed_windows() {
+            recovered[window] = Some(blocks);
+        }
+
+        let recovered: Vec<Vec<i32>> = recovered.into_iter().flat_map(Option::unwrap).collect();
+        assert_eq!(recovered, source_blocks);
+
+        // Flushed and unknown windows ignore blocks
+        assert_eq!(decoder.add_encoded_block(0, 5, 1, vec![0; 3]), None);
+        assert_eq!(decoder.add_encoded_block(9, 5, 1, vec![0; 3]), None);
+
+        assert!(matches!(WindowedDecoder::new(k, 0, 3), Err(ConfigError::ZeroWindowSize)));
+    }
+}
@@ -0,0 +1,290 @@
+/// Number of symbols XORed per unrolled step
+const LANES: usize = 8;
+
+/// Bytes XORed per word by `xor_bytes`
+const WORD: usize = core::mem::size_of::<u128>();
+
+/// Element type of source and encoded blocks
+///
+/// Encoders and decoders only ever XOR symbols together and pad blocks with
+/// `ZERO`, so any fixed-size value with an XOR works: plain integers, arrays
+/// of them, or custom types. Wider types XOR more bits per operation; `u8`
+/// avoids widening byte payloads. `i32` is the default used throughout the
+/// crate and the WASM bindings.
+pub trait XorElement: Copy + Send + Sync {
+    /// The identity of `xor_assign`, used for padding
+    const ZERO: Self;
+
+    /// XORs `other` into `self`
+    fn xor_assign(&mut self, other: &Self);
+
+    /// XORs `src` into `dst`, which have the same length
+    ///
+    /// The default works on fixed-size chunks the compiler can turn into
+    /// wide vector operations, dispatched like `xor_bytes`; types with a
+    /// faster kernel override it.
+    fn xor_slice(dst: &mut [Self], src: &[Self]) {
+        #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+        if std::is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports AVX2, checked just above
+            unsafe { xor_lanes_avx2(dst, src) };
+            return;
+        }
+        #[cfg(all(target_arch = "wasm32", feature = "simd128"))]
+        #[allow(unused_unsafe)]
+        // SAFETY: building with `simd128` requires a runtime with SIMD128
+        return unsafe { xor_lanes_simd128(dst, src) };
+        #[allow(unreachable_code)]
+        xor_lanes(dst, src);
+    }
+}
+
+/// Scalar kernel behind the default `XorElement::xor_slice`
+#[inline(always)]
+fn xor_lanes<T: XorElement>(dst: &mut [T], src: &[T]) {
+    let mut dst_chunks = dst.chunks_exact_mut(LANES);
+    let mut src_chunks = src.chunks_exact(LANES);
+
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        let d: &mut [T; LANES] = d.try_into().unwrap();
+        let s: &[T; LANES] = s.try_into().unwrap();
+        for (d, s) in d.iter_mut().zip(s) {
+            d.xor_assign(s);
+        }
+    }
+
+    for (d, s) in dst_chunks.into_remainder().iter_mut().zip(src_chunks.remainder()) {
+        d.xor_assign(s);
+    }
+}
+
+/// `xor_lanes` compiled for 256-bit AVX2 registers
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+#[target_feature(enable = "avx2")]
+fn xor_lanes_avx2<T: XorElement>(dst: &mut [T], src: &[T]) {
+    xor_lanes(dst, src);
+}
+
+/// `xor_lanes` compiled for wasm32 SIMD128
+#[cfg(all(target_arch = "wasm32", feature = "simd128"))]
+#[target_feature(enable = "simd128")]
+fn xor_lanes_simd128<T: XorElement>(dst: &mut [T], src: &[T]) {
+    xor_lanes(dst, src);
+}
+
+macro_rules! impl_xor_element {
+    ($($ty:ty),*) => {
+        $(
+            impl XorElement for $ty {
+                const ZERO: Self = 0;
+
+                #[inline]
+                fn xor_assign(&mut self, other: &Self) {
+                    *self ^= *other;
+                }
+            }
+        )*
+    };
+}
+
+impl_xor_element!(u16, u32, u64, u128, i8, i16, i32, i64);
+
+// Bytes go through `xor_bytes`, 16 at a time
+impl XorElement for u8 {
+    const ZERO: Self = 0;
+
+    #[inline]
+    fn xor_assign(&mut self, other: &Self) {
+        *self ^= *other;
+    }
+
+    fn xor_slice(dst: &mut [Self], src: &[Self]) {
+        xor_bytes(dst, src);
+    }
+}
+
+// Arrays XOR element-wise, e.g. `[u8; 16]` for 16-byte symbols
+impl<T: XorElement, const N: usize> XorElement for [T; N] {
+    const ZERO: Self = [T::ZERO; N];
+
+    #[inline]
+    fn xor_assign(&mut self, other: &Self) {
+        for (a, b) in self.iter_mut().zip(other) {
+            a.xor_assign(b);
+        }
+    }
+}
+
+/// XORs `src` into `dst` element-wise, see `XorElement::xor_slice`
+///
+/// `dst.len()` bounds the operation: symbols of `src` past the end of `dst`
+/// are ignored, and if `src` is shorter only its length of `dst` is touched.
+/// Callers that need equal lengths must check them first.
+pub fn xor_into<T: XorElement>(dst: &mut [T], src: &[T]) {
+    let len = dst.len().min(src.len());
+    T::xor_slice(&mut dst[..len], &src[..len]);
+}
+
+/// XORs `src` into `dst` as `u128` words, finishing the tail byte by byte
+///
+/// Lengths are handled as in `xor_into`. Unaligned words are loaded and
+/// stored through byte arrays, which compiles to plain wide moves.
+///
+/// SSE2 and NEON belong to the x86_64 and aarch64 baselines, so the words
+/// already map to their 128-bit registers there. With `std` on x86, CPUs
+/// with AVX2, detected at runtime, get a copy of the kernel compiled for
+/// 256-bit registers. On wasm32 the `simd128` feature compiles the kernels
+/// for 128-bit SIMD, which the runtime must then support.
+pub fn xor_bytes(dst: &mut [u8], src: &[u8]) {
+    let len = dst.len().min(src.len());
+    #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2, checked just above
+        unsafe { xor_words_avx2(&mut dst[..len], &src[..len]) };
+        return;
+    }
+    #[cfg(all(target_arch = "wasm32", feature = "simd128"))]
+    #[allow(unused_unsafe)]
+    // SAFETY: building with `simd128` requires a runtime with SIMD128
+    return unsafe { xor_words_simd128(&mut dst[..len], &src[..len]) };
+    #[allow(unreachable_code)]
+    xor_words(&mut dst[..len], &src[..len]);
+}
+
+/// Scalar kernel behind `xor_bytes`, for slices of equal length
+#[inline(always)]
+fn xor_words(dst: &mut [u8], src: &[u8]) {
+    let mut dst_words = dst.chunks_exact_mut(WORD);
+    let mut src_words = src.chunks_exact(WORD);
+
+    for (d, s) in (&mut dst_words).zip(&mut src_words) {
+        let word = u128::from_ne_bytes(d.try_into().unwrap()) ^ u128::from_ne_bytes(s.try_into().unwrap());
+        d.copy_from_slice(&word.to_ne_bytes());
+    }
+
+    for (d, s) in dst_words.into_remainder().iter_mut().zip(src_words.remainder()) {
+        *d ^= s;
+    }
+}
+
+/// `xor_words` compiled for 256-bit AVX2 registers
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+#[target_feature(enable = "avx2")]
+fn xor_words_avx2(dst: &mut [u8], src: &[u8]) {
+    xor_words(dst, src);
+}
+
+/// `xor_words` compiled for wasm32 SIMD128
+#[cfg(all(target_arch = "wasm32", feature = "simd128"))]
+#[target_feature(enable = "simd128")]
+fn xor_words_simd128(dst: &mut [u8], src: &[u8]) {
+    xor_words(dst, src);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xor_into_matches_naive() {
+        // Deterministic pseudo-random slices of assorted lengths
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..200 {
+            let dst_len = (next() % 70) as usize;
+            let src_len = (next() % 70) as usize;
+            let dst: Vec<i32> = (0..dst_len).map(|_| next() as i32).collect();
+            let src: Vec<i32> = (0..src_len).map(|_| next() as i32).collect();
+
+            let mut expected = dst.clone();
+            for i in 0..dst_len.min(src_len) {
+                expected[i] ^= src[i];
+            }
+
+            let mut actual = dst.clone();
+            xor_into(&mut actual, &src);
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_xor_bytes() {
+        for (dst_len, src_len) in [(0, 5), (15, 15), (16, 16), (37, 40), (40, 37), (100, 100)] {
+            let dst: Vec<u8> = (0..dst_len).map(|i| (i * 7) as u8).collect();
+            let src: Vec<u8> = (0..src_len).map(|i| (i * 13 + 1) as u8).collect();
+            let mut expected = dst.clone();
+            for (d, s) in expected.iter_mut().zip(&src) {
+                *d ^= s;
+            }
+
+            let mut actual = dst.clone();
+            xor_bytes(&mut actual, &src);
+            assert_eq!(actual, expected);
+
+            // `u8` blocks take the same path through `xor_into`
+            let mut generic = dst;
+            xor_into(&mut generic, &src);
+            assert_eq!(generic, expected);
+        }
+    }
+
+    #[test]
+    fn test_kernels_agree() {
+        // Every kernel a build can dispatch to, not only the one this CPU picks
+        for len in 0..70 {
+            let dst: Vec<u8> = (0..len).map(|i| (i * 31 + 5) as u8).collect();
+            let src: Vec<u8> = (0..len).map(|i| (i * 17 + 3) as u8).collect();
+            let expected: Vec<u8> = dst.iter().zip(&src).map(|(d, s)| d ^ s).collect();
+            let dst_words: Vec<u32> = dst.iter().map(|&b| u32::from(b) * 0x0101_0101).collect();
+            let src_words: Vec<u32> = src.iter().map(|&b| u32::from(b) << 8 | 0x8000_0001).collect();
+            let expected_words: Vec<u32> = dst_words.iter().zip(&src_words).map(|(d, s)| d ^ s).collect();
+
+            let mut actual = dst.clone();
+            xor_words(&mut actual, &src);
+            assert_eq!(actual, expected);
+            let mut actual = dst_words.clone();
+            xor_lanes(&mut actual, &src_words);
+            assert_eq!(actual, expected_words);
+
+            #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+            if std::is_x86_feature_detected!("avx2") {
+                let mut actual = dst.clone();
+                // SAFETY: the CPU supports AVX2, checked just above
+                unsafe { xor_words_avx2(&mut actual, &src) };
+                assert_eq!(actual, expected);
+                let mut actual = dst_words.clone();
+                // SAFETY: as above
+                unsafe { xor_lanes_avx2(&mut actual, &src_words) };
+                assert_eq!(actual, expected_words);
+            }
+
+            #[cfg(all(target_arch = "wasm32", feature = "simd128"))]
+            {
+                let mut actual = dst.clone();
+                // SAFETY: building with `simd128` requires a runtime with SIMD128
+                #[allow(unused_unsafe)]
+                unsafe { xor_words_simd128(&mut actual, &src) };
+                assert_eq!(actual, expected);
+                let mut actual = dst_words.clone();
+                // SAFETY: as above
+                #[allow(unused_unsafe)]
+                unsafe { xor_lanes_simd128(&mut actual, &src_words) };
+                assert_eq!(actual, expected_words);
+            }
+        }
+    }
+
+    #[test]
+    fn test_array_elements() {
+        let mut dst = vec![[1u8, 2, 3], [4, 5, 6]];
+        xor_into(&mut dst, &[[1, 1, 1], [6, 5, 4]]);
+        assert_eq!(dst, vec![[0, 3, 2], [2, 0, 2]]);
+        assert_eq!(<[u16; 4]>::ZERO, [0; 4]);
+    }
+}
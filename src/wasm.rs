@@ -0,0 +1,1167 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Array;
+use std::collections::BTreeSet;
+
+use crate::luby_transform;
+use crate::luby_transform::wire::{self, Reader};
+use crate::{block_crc, pack_blocks, unpack_blocks, ByteOrder, ConfigError, Decoder, EncodeError, Encoder, LtError, ParseError};
+
+// Size of the fixed `EncodedBlock` wire header: seed, degree, payload length
+const BLOCK_HEADER_LEN: usize = 16;
+
+// Surface codec errors to JS as `Error` objects
+impl From<EncodeError> for JsValue {
+    fn from(err: EncodeError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+impl From<ParseError> for JsValue {
+    fn from(err: ParseError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+impl From<LtError> for JsValue {
+    fn from(err: LtError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+#[wasm_bindgen]
+pub struct LubyTransformEncoder {
+    encoder: Encoder,
+    checksums: bool,                   // Attach a CRC32 to every generated block
+    byte_lens: Option<(usize, usize)>, // Block and total input length for `from_bytes`
+}
+
+#[wasm_bindgen]
+pub struct LubyTransformDecoder {
+    decoder: Decoder,
+}
+
+/// Encoder working on raw bytes, one `u8` symbol per byte
+///
+/// Unlike `LubyTransformEncoder::from_bytes` nothing is packed into `i32`
+/// symbols; blocks are plain byte slices of the input.
+#[wasm_bindgen]
+pub struct LubyTransformByteEncoder {
+    encoder: Encoder<u8>,
+}
+
+/// Decoder for blocks produced by `LubyTransformByteEncoder`
+#[wasm_bindgen]
+pub struct LubyTransformByteDecoder {
+    decoder: Decoder<u8>,
+}
+
+/// Encoded block with a byte payload, see `LubyTransformByteEncoder`
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodedByteBlock {
+    seed: i64,
+    degree: usize,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodedBlock {
+    seed: i64,
+    degree: usize,
+    indices: Vec<usize>,
+    data: Vec<i32>,
+    crc: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl EncodedBlock {
+    /// Creates a block from its wire fields, without indices
+    ///
+    /// This is all a decoder needs: it recomputes the indices from `seed`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: i64, degree: usize, data: &[i32]) -> Self {
+        Self {
+            seed,
+            degree,
+            indices: Vec::new(),
+            data: data.to_vec(),
+            crc: None,
+        }
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+    
+    /// Source block indices the block combines, sorted
+    ///
+    /// Indices are derived from `seed`, so they need not be transmitted and
+    /// decoders never read them. Blocks from the constructor or `from_bytes`
+    /// start without indices until they are set.
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Vec<usize> {
+        self.indices.clone()
+    }
+    
+    /// Attaches the source block indices, e.g. for display
+    #[wasm_bindgen(setter)]
+    pub fn set_indices(&mut self, mut indices: Vec<usize>) {
+        indices.sort_unstable();
+        self.indices = indices;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> js_sys::Int32Array {
+        js_sys::Int32Array::from(&self.data[..])
+    }
+    
+    /// The CRC32 attached to the block, if any
+    #[wasm_bindgen(getter)]
+    pub fn crc(&self) -> Option<u32> {
+        self.crc
+    }
+    
+    /// Returns false only if a CRC32 is attached and does not match the
+    /// block contents
+    pub fn verify_crc(&self) -> bool {
+        self.crc.is_none_or(|crc| crc == block_crc(self.seed, self.degree, &self.data))
+    }
+    
+    /// Serializes the block as a little-endian header (8-byte seed, 4-byte
+    /// degree, 4-byte payload length in symbols) followed by the `i32` payload
+    /// and, if attached, the 4-byte CRC32
+    ///
+    /// `indices` are not written; the decoder derives them from the seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(BLOCK_HEADER_LEN + self.data.len() * 4 + 4);
+        wire::put_i64(&mut out, self.seed);
+        wire::put_u32(&mut out, self.degree as u32);
+        wire::put_u32(&mut out, self.data.len() as u32);
+        wire::put_i32s(&mut out, &self.data);
+        if let Some(crc) = self.crc {
+            wire::put_u32(&mut out, crc);
+        }
+        out
+    }
+    
+    /// Parses a block written by `to_bytes`, rejecting truncated buffers and
+    /// payload lengths that disagree with the buffer size
+    ///
+    /// Exactly four bytes past the payload are read as the CRC32. The
+    /// checksum is not verified here; see `verify_crc`.
+    pub fn from_bytes(buf: &[u8]) -> Result<EncodedBlock, ParseError> {
+        let truncated = ParseError::TruncatedHeader { len: buf.len() };
+        let mut reader = Reader::new(buf);
+        let seed = reader.i64().ok_or(truncated.clone())?;
+        let degree = reader.u32().ok_or(truncated.clone())? as usize;
+        let len = reader.u32().ok_or(truncated)? as usize;
+        
+        let declared = len.saturating_mul(4);
+        let available = reader.remaining();
+        let mismatch = ParseError::LengthMismatch { declared, available };
+        if declared != available && declared.checked_add(4) != Some(available) {
+            return Err(mismatch);
+        }
+        let data = reader.i32s(len).ok_or(mismatch)?;
+        let crc = reader.u32();
+        
+        Ok(Self {
+            seed,
+            degree,
+            indices: Vec::new(),
+            data,
+            crc,
+        })
+    }
+}
+
+// Internal implementation not exposed to JS
+impl EncodedBlock {
+    // Internal method for creating with indices (not exposed to JS)
+    pub fn new_with_indices(seed: i64, degree: usize, indices: BTreeSet<usize>, data: Vec<i32>) -> Self {
+        // Sorted so equal blocks compare equal regardless of hash order
+        let mut indices: Vec<usize> = indices.into_iter().collect();
+        indices.sort_unstable();
+        Self {
+            seed,
+            degree,
+            indices,
+            data,
+            crc: None,
+        }
+    }
+    
+    /// Attaches the CRC32 of the block contents, see `block_crc`
+    pub fn with_crc(mut self) -> Self {
+        self.crc = Some(block_crc(self.seed, self.degree, &self.data));
+        self
+    }
+    
+    /// The block payload as `i32` symbols
+    pub fn symbols(&self) -> &[i32] {
+        &self.data
+    }
+}
+#[wasm_bindgen]
+impl EncodedByteBlock {
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: i64, degree: usize, data: &[u8]) -> Self {
+        Self {
+            seed,
+            degree,
+            data: data.to_vec(),
+        }
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(&self.data[..])
+    }
+}
+
+#[wasm_bindgen]
+impl LubyTransformByteEncoder {
+    /// Splits `data` into source blocks of `block_size` bytes and creates an
+    /// encoder over them
+    ///
+    /// The last block is zero-padded for encoding. Throws if `data` or
+    /// `block_size` is zero.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8], block_size: usize, seed: Option<i64>) -> Result<LubyTransformByteEncoder, JsValue> {
+        Ok(Self::from_data(data, block_size, seed)?)
+    }
+    
+    pub fn generate_block(&mut self, seed: Option<i64>) -> Result<EncodedByteBlock, JsValue> {
+        let (blockseed, degree, _, data) = self.encoder.generate_encoded_block(seed)?;
+        Ok(EncodedByteBlock { seed: blockseed, degree, data })
+    }
+    
+    /// Generates the next `n` blocks as an array of `EncodedByteBlock`s
+    pub fn generate_blocks(&mut self, n: usize) -> Result<Array, JsValue> {
+        Ok(self.next_blocks(n)?.into_iter().map(JsValue::from).collect())
+    }
+    
+    pub fn source_block_count(&self) -> usize {
+        self.encoder.source_block_count()
+    }
+    
+    /// Length of each source block, in symbols
+    pub fn block_size(&self) -> usize {
+        self.encoder.block_size()
+    }
+    
+    /// Number of blocks generated so far, repair blocks excluded
+    pub fn packets_generated(&self) -> usize {
+        self.encoder.packets_generated()
+    }
+    
+    /// Generates one degree-1 `EncodedByteBlock` per missing source index
+    pub fn generate_repair_for(&self, indices: &[usize]) -> Result<Array, JsValue> {
+        let blocks = self.encoder.generate_repair_for(indices).map_err(LtError::from)?;
+        Ok(blocks
+            .into_iter()
+            .map(|(seed, degree, _, data)| JsValue::from(EncodedByteBlock { seed, degree, data }))
+            .collect())
+    }
+    
+    /// Sends the source blocks verbatim before any repair blocks, restarting
+    /// from the first source block when switched on
+    pub fn set_systematic(&mut self, enabled: bool) {
+        self.encoder.set_systematic(enabled);
+    }
+}
+
+#[wasm_bindgen]
+impl LubyTransformByteDecoder {
+    /// Creates a decoder for `data_len` bytes sent in blocks of `block_size`
+    /// bytes, the parameters the encoder was created with
+    ///
+    /// Throws if `block_size` is zero.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data_len: usize, block_size: usize) -> Result<LubyTransformByteDecoder, JsValue> {
+        Ok(Self::from_len(data_len, block_size).map_err(LtError::from)?)
+    }
+    
+    pub fn add_encoded_block(&mut self, seed: i64, degree: usize, data: &[u8]) -> Option<usize> {
+        self.decoder.add_encoded_block(seed, degree, data.to_vec())
+    }
+    
+    pub fn add_block(&mut self, block: &EncodedByteBlock) -> Option<usize> {
+        self.decoder.add_encoded_block(block.seed, block.degree, block.data.clone())
+    }
+    
+    pub fn decoded_count(&self) -> usize {
+        self.decoder.decoded_count()
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.decoder.is_complete()
+    }
+    
+    pub fn missing_indices(&self) -> Vec<usize> {
+        self.decoder.missing_indices()
+    }
+    
+    /// Returns the blocks decoded since the last call as an array of
+    /// `[index, Uint8Array]` pairs
+    pub fn drain_newly_decoded(&mut self) -> Array {
+        self.decoder
+            .drain_newly_decoded()
+            .into_iter()
+            .map(|(index, block)| {
+                let pair = Array::new();
+                pair.push(&JsValue::from(index));
+                pair.push(&js_sys::Uint8Array::from(&block[..]));
+                JsValue::from(pair)
+            })
+            .collect()
+    }
+    
+    /// Returns an array with a `Uint8Array` for every decoded block and
+    /// `null` for the missing ones, to salvage an incomplete transfer
+    pub fn get_partial_blocks(&self) -> Array {
+        self.decoder
+            .get_partial_blocks()
+            .into_iter()
+            .map(|block| block.map_or(JsValue::NULL, |block| js_sys::Uint8Array::from(&block[..]).into()))
+            .collect()
+    }
+    
+    /// Returns the original data once every block is decoded
+    pub fn get_decoded_bytes(&self) -> Option<js_sys::Uint8Array> {
+        self.decoded_bytes().map(|bytes| js_sys::Uint8Array::from(&bytes[..]))
+    }
+}
+
+#[wasm_bindgen]
+impl LubyTransformEncoder {
+    /// Creates an encoder from an array of `Int32Array` source blocks
+    ///
+    /// Throws if the array is empty, if an entry is not an `Int32Array`,
+    /// naming its index, or if any block is empty.
+    #[wasm_bindgen(constructor)]
+    pub fn new(source_blocks: js_sys::Array, seed: Option<i64>) -> Result<LubyTransformEncoder, JsValue> {
+        Ok(Self::from_source(int32_blocks(source_blocks)?, seed)?)
+    }
+    
+    /// Creates an encoder from an array of `Uint8Array` source blocks
+    ///
+    /// Bytes are packed four per `i32` symbol in `order` (little-endian by
+    /// default) instead of being widened one per symbol. Decode with a
+    /// `LubyTransformDecoder` whose `block_size` is the symbol count, i.e. the
+    /// byte length rounded up to a multiple of four and divided by four.
+    /// Every block but the last must have the same length; the last may be
+    /// shorter and is zero-padded. Pass `block_byte_len` and `byte_len` to
+    /// `LubyTransformDecoder::get_decoded_packed_bytes` to drop the padding.
+    /// Throws if an entry is not a `Uint8Array`, naming its index, and on the
+    /// same input as the constructor.
+    pub fn from_bytes(source_blocks: js_sys::Array, seed: Option<i64>, order: Option<ByteOrder>) -> Result<LubyTransformEncoder, JsValue> {
+        let byte_blocks = typed_blocks(source_blocks, "Uint8Array", |bytes: js_sys::Uint8Array| bytes.to_vec())?;
+        Ok(Self::from_byte_blocks(byte_blocks, seed, order.unwrap_or_default())?)
+    }
+    
+    /// Length in bytes of each block given to `from_bytes` but the last, or
+    /// `undefined` for an encoder built from `Int32Array`s
+    pub fn block_byte_len(&self) -> Option<usize> {
+        self.byte_lens.map(|(block, _)| block)
+    }
+    
+    /// Total unpadded length in bytes of the blocks given to `from_bytes`,
+    /// or `undefined` for an encoder built from `Int32Array`s
+    pub fn byte_len(&self) -> Option<usize> {
+        self.byte_lens.map(|(_, total)| total)
+    }
+    
+    /// Generates block `index` of the sequence rooted at the encoder's seed,
+    /// independently of earlier calls
+    pub fn generate_block_at(&mut self, index: usize) -> Result<EncodedBlock, JsValue> {
+        let (blockseed, d, indices, encoded_block) = self.encoder.generate_block_at(index)?;
+        let block = EncodedBlock::new_with_indices(blockseed, d, indices, encoded_block);
+        Ok(if self.checksums { block.with_crc() } else { block })
+    }
+    
+    /// Attaches a CRC32 to every block generated from now on
+    pub fn set_checksums(&mut self, enabled: bool) {
+        self.checksums = enabled;
+    }
+    
+    pub fn generate_block(&mut self, seed: Option<i64>) -> Result<EncodedBlock, JsValue> {
+        let (blockseed, d, indices, encoded_block) = self.encoder.generate_encoded_block(seed)?;
+        let block = EncodedBlock::new_with_indices(blockseed, d, indices, encoded_block);
+        Ok(if self.checksums { block.with_crc() } else { block })
+    }
+    
+    /// Generates the next `n` blocks as an array of `EncodedBlock`s, with
+    /// CRCs if checksums are enabled
+    pub fn generate_blocks(&mut self, n: usize) -> Result<Array, JsValue> {
+        Ok(self.next_blocks(n)?.into_iter().map(JsValue::from).collect())
+    }
+    
+    pub fn source_block_count(&self) -> usize {
+        self.encoder.source_block_count()
+    }
+    
+    /// Length of each source block, in symbols
+    pub fn block_size(&self) -> usize {
+        self.encoder.block_size()
+    }
+    
+    /// Number of blocks generated so far, repair blocks excluded
+    pub fn packets_generated(&self) -> usize {
+        self.encoder.packets_generated()
+    }
+    
+    /// Generates one degree-1 `EncodedBlock` per source index a receiver is
+    /// missing, e.g. the unset bits of `LubyTransformDecoder::feedback`
+    pub fn generate_repair_for(&self, indices: &[usize]) -> Result<Array, JsValue> {
+        let blocks = self.encoder.generate_repair_for(indices).map_err(LtError::from)?;
+        Ok(blocks
+            .into_iter()
+            .map(|(blockseed, d, indices, data)| {
+                let block = EncodedBlock::new_with_indices(blockseed, d, indices, data);
+                JsValue::from(if self.checksums { block.with_crc() } else { block })
+            })
+            .collect())
+    }
+    
+    /// Sends the source blocks verbatim before any repair blocks, restarting
+    /// from the first source block when switched on
+    pub fn set_systematic(&mut self, enabled: bool) {
+        self.encoder.set_systematic(enabled);
+    }
+    
+    /// Skips blocks the receiver no longer needs, given the bitmap from
+    /// `LubyTransformDecoder::feedback`; throws if its length is wrong
+    pub fn apply_feedback(&mut self, feedback: &[u8]) -> Result<(), JsValue> {
+        Ok(self.encoder.apply_feedback(feedback).map_err(LtError::from)?)
+    }
+    
+    /// Total unpadded length of the source blocks in symbols, for
+    /// `LubyTransformDecoder::set_data_len`
+    pub fn data_len(&self) -> usize {
+        self.encoder.data_len()
+    }
+}
+
+#[wasm_bindgen]
+impl LubyTransformDecoder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(k: usize, block_size: usize) -> Self {
+        Self {
+            decoder: Decoder::new_default(k, block_size),
+        }
+    }
+    
+    /// Creates a decoder that trims the final block to `last_block_size`
+    pub fn with_last_block_size(k: usize, block_size: usize, last_block_size: usize) -> Self {
+        Self {
+            decoder: Decoder::new_with_last_block_size(k, block_size, last_block_size),
+        }
+    }
+    
+    /// Serializes the decoder state, e.g. to keep it in IndexedDB across a
+    /// page reload and resume with `restore_state`
+    pub fn serialize_state(&self) -> Vec<u8> {
+        self.decoder.serialize_state()
+    }
+    
+    /// Rebuilds a decoder from bytes written by `serialize_state`; throws
+    /// if they are not a valid state
+    pub fn restore_state(bytes: &[u8]) -> Result<LubyTransformDecoder, JsValue> {
+        Ok(Self { decoder: Decoder::restore_state(bytes)? })
+    }
+    
+    /// Sets the original data length in symbols so the final block's
+    /// padding is dropped; throws if it does not fit the decoder's blocks
+    pub fn set_data_len(&mut self, data_len: usize) -> Result<(), JsValue> {
+        Ok(self.decoder.set_data_len(data_len).map_err(LtError::from)?)
+    }
+    
+    pub fn add_encoded_block(&mut self, seed: i64, degree: usize, data: &[i32]) -> Option<usize> {
+        self.decoder.add_encoded_block(seed, degree, data.to_vec())
+    }
+    
+    /// Adds an encoded block and returns how many source blocks it decoded
+    pub fn add_encoded_block_report(&mut self, seed: i64, degree: usize, data: &[i32]) -> usize {
+        self.decoder.add_encoded_block_report(seed, degree, data.to_vec()).newly_decoded
+    }
+    
+    /// Adds an array of `[seed, degree, Int32Array]` triples and peels once
+    /// over all of them, see `Decoder::add_blocks_batch`
+    ///
+    /// Malformed entries are skipped. Returns how many source blocks the
+    /// batch decoded.
+    pub fn add_blocks_batch(&mut self, blocks: js_sys::Array) -> usize {
+        let blocks = blocks
+            .iter()
+            .filter_map(|entry| {
+                let entry = entry.dyn_into::<Array>().ok()?;
+                let seed = entry.get(0).as_f64()? as i64;
+                let degree = entry.get(1).as_f64()? as usize;
+                let data = entry.get(2).dyn_into::<js_sys::Int32Array>().ok()?.to_vec();
+                Some((seed, degree, data))
+            })
+            .collect();
+        
+        let before = self.decoder.decoded_count();
+        self.decoder.add_blocks_batch(blocks);
+        self.decoder.decoded_count() - before
+    }
+    
+    /// Adds a block whose neighbours are given explicitly, e.g. from another
+    /// LT implementation, instead of derived from `seed`
+    pub fn add_encoded_block_with_indices(&mut self, seed: i64, indices: &[usize], data: &[i32]) -> Option<usize> {
+        self.decoder.add_encoded_block_with_indices(seed, indices, data.to_vec())
+    }
+    
+    /// Adds an `EncodedBlock`, dropping it if its attached CRC32 does not
+    /// match
+    pub fn add_block(&mut self, block: &EncodedBlock) -> Option<usize> {
+        match block.crc {
+            Some(crc) => self.decoder.add_encoded_block_with_crc(block.seed, block.degree, block.data.clone(), crc),
+            None => self.decoder.add_encoded_block(block.seed, block.degree, block.data.clone()),
+        }
+    }
+    
+    /// Adds an encoded block, calling `cb(decoded_count, k)` after every
+    /// decoding round and when the decode completes
+    ///
+    /// Exceptions thrown by `cb` are caught and ignored so they cannot leave
+    /// the decoder half-updated.
+    pub fn add_encoded_block_with_progress(&mut self, seed: i64, degree: usize, data: &[i32], cb: &js_sys::Function) -> Option<usize> {
+        self.decoder.add_encoded_block_with_progress(seed, degree, data.to_vec(), |decoded, k| {
+            let _ = cb.call2(&JsValue::NULL, &JsValue::from(decoded), &JsValue::from(k));
+        })
+    }
+    
+    pub fn decoded_count(&self) -> usize {
+        self.decoder.decoded_count()
+    }
+    
+    pub fn k(&self) -> usize {
+        self.decoder.k()
+    }
+    
+    pub fn block_size(&self) -> usize {
+        self.decoder.block_size()
+    }
+    
+    /// Received blocks still buffered for peeling
+    pub fn pending_count(&self) -> usize {
+        self.decoder.pending_count()
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.decoder.is_complete()
+    }
+    
+    pub fn missing_indices(&self) -> Vec<usize> {
+        self.decoder.missing_indices()
+    }
+    
+    /// Bitmap of decoded source blocks for the encoder's `apply_feedback`
+    pub fn feedback(&self) -> Vec<u8> {
+        self.decoder.feedback()
+    }
+    
+    /// True when no held block can make progress; send more blocks
+    pub fn is_stalled(&self) -> bool {
+        self.decoder.is_stalled()
+    }
+    
+    pub fn reducible_blocks(&self) -> usize {
+        self.decoder.reducible_blocks()
+    }
+    
+    /// Returns the source blocks decoded since the last call as an array of
+    /// `[index, Int32Array]` pairs, so partial results can be used early
+    pub fn drain_newly_decoded(&mut self) -> Array {
+        self.decoder
+            .drain_newly_decoded()
+            .into_iter()
+            .map(|(index, block)| {
+                let pair = Array::new();
+                pair.push(&JsValue::from(index));
+                pair.push(&js_sys::Int32Array::from(&block[..]));
+                JsValue::from(pair)
+            })
+            .collect()
+    }
+    
+    /// Returns an array with an `Int32Array` for every decoded block and
+    /// `null` for the missing ones, to salvage an incomplete transfer
+    pub fn get_partial_blocks(&self) -> Array {
+        self.decoder
+            .get_partial_blocks()
+            .into_iter()
+            .map(|block| block.map_or(JsValue::NULL, |block| js_sys::Int32Array::from(&block[..]).into()))
+            .collect()
+    }
+    
+    pub fn get_all_decoded_blocks(&self) -> Option<Array> {
+        if let Some(blocks) = self.decoder.get_all_decoded_blocks() {
+            let js_array = Array::new();
+            for block in blocks {
+                js_array.push(&js_sys::Int32Array::from(&block[..]));
+            }
+            Some(js_array)
+        } else {
+            None
+        }
+    }
+    
+    /// Returns the decoded blocks as `Uint8Array`s, unpacking each symbol into
+    /// four bytes in `order` (little-endian by default)
+    ///
+    /// Use this with encoders built by `LubyTransformEncoder::from_bytes`;
+    /// each block keeps the zero padding of its final symbol.
+    pub fn get_all_decoded_byte_blocks(&self, order: Option<ByteOrder>) -> Option<Array> {
+        let blocks = self.decoder.get_all_decoded_blocks()?;
+        let js_array = Array::new();
+        for block in unpack_blocks(&blocks, order.unwrap_or_default()) {
+            js_array.push(&js_sys::Uint8Array::from(&block[..]));
+        }
+        Some(js_array)
+    }
+    
+    /// Returns the data of a `LubyTransformEncoder::from_bytes` encoder as
+    /// one `Uint8Array`, unpacked in `order` (little-endian by default)
+    ///
+    /// Each block is cut to `block_byte_len` and the result to `byte_len`,
+    /// the encoder's values, dropping the padding of every final symbol.
+    pub fn get_decoded_packed_bytes(&self, block_byte_len: usize, byte_len: usize, order: Option<ByteOrder>) -> Option<js_sys::Uint8Array> {
+        let bytes = self.packed_bytes(block_byte_len, byte_len, order.unwrap_or_default())?;
+        Some(js_sys::Uint8Array::from(&bytes[..]))
+    }
+    
+    /// Returns the decoded data as one `Uint8Array`, all `k` blocks
+    /// concatenated in order
+    ///
+    /// Each symbol must hold a single byte value, as with blocks built from
+    /// JS byte arrays by `LubyTransformEncoder::new`; it is narrowed to its
+    /// low byte. The final block is trimmed if its length was given to
+    /// `with_last_block_size`.
+    pub fn get_decoded_bytes(&self) -> Option<js_sys::Uint8Array> {
+        self.decoded_bytes().map(|bytes| js_sys::Uint8Array::from(&bytes[..]))
+    }
+    
+    /// Returns the peeling graph as a plain object
+    /// `{ blocks: [[blockIndex, [sourceIndex, ...]], ...], decoded: [sourceIndex, ...] }`
+    /// that `JSON.stringify` accepts, see `Decoder::graph_snapshot`
+    pub fn graph_snapshot(&self) -> Result<js_sys::Object, JsValue> {
+        let blocks = Array::new();
+        for (block_idx, undecoded) in self.decoder.graph_snapshot() {
+            let edges: Array = undecoded.into_iter().map(JsValue::from).collect();
+            blocks.push(&Array::of2(&JsValue::from(block_idx), &edges));
+        }
+        let decoded: Array = self.decoder.decoded_indices().into_iter().map(JsValue::from).collect();
+        
+        let graph = js_sys::Object::new();
+        js_sys::Reflect::set(&graph, &JsValue::from_str("blocks"), &blocks)?;
+        js_sys::Reflect::set(&graph, &JsValue::from_str("decoded"), &decoded)?;
+        Ok(graph)
+    }
+    
+    pub fn current_round(&self) -> usize {
+        self.decoder.current_round()
+    }
+    
+    pub fn blocks_received(&self) -> usize {
+        self.decoder.blocks_received()
+    }
+    
+    /// Blocks dropped as retransmissions of an already received seed
+    pub fn duplicates_received(&self) -> usize {
+        self.decoder.duplicates_received()
+    }
+    
+    pub fn overhead_ratio(&self) -> f64 {
+        self.decoder.overhead_ratio()
+    }
+    
+    pub fn redundant_blocks(&self) -> usize {
+        self.decoder.redundant_blocks()
+    }
+    
+    pub fn blocks_corrupted(&self) -> usize {
+        self.decoder.blocks_corrupted()
+    }
+}
+
+impl LubyTransformEncoder {
+    fn from_source(source_blocks: Vec<Vec<i32>>, seed: Option<i64>) -> Result<Self, EncodeError> {
+        check_source(&source_blocks)?;
+        Ok(Self {
+            encoder: Encoder::new_default(source_blocks, seed),
+            checksums: false,
+            byte_lens: None,
+        })
+    }
+    
+    fn from_byte_blocks(byte_blocks: Vec<Vec<u8>>, seed: Option<i64>, order: ByteOrder) -> Result<Self, EncodeError> {
+        check_source(&byte_blocks)?;
+        let (last, expected) = (byte_blocks.len() - 1, byte_blocks[0].len());
+        let uneven = byte_blocks
+            .iter()
+            .enumerate()
+            .find(|&(index, block)| block.len() > expected || (index < last && block.len() != expected));
+        if let Some((index, block)) = uneven {
+            return Err(EncodeError::MismatchedBlockLength { index, expected, found: block.len() });
+        }
+        Ok(Self {
+            encoder: Encoder::new_padded(pack_blocks(&byte_blocks, order), seed),
+            checksums: false,
+            byte_lens: Some((expected, byte_blocks.iter().map(Vec::len).sum())),
+        })
+    }
+    
+    fn next_blocks(&mut self, n: usize) -> Result<Vec<EncodedBlock>, EncodeError> {
+        let blocks = self.encoder.generate_encoded_blocks(n)?;
+        Ok(blocks
+            .into_iter()
+            .map(|(blockseed, d, indices, data)| {
+                let block = EncodedBlock::new_with_indices(blockseed, d, indices, data);
+                if self.checksums { block.with_crc() } else { block }
+            })
+            .collect())
+    }
+}
+
+impl LubyTransformByteEncoder {
+    fn next_blocks(&mut self, n: usize) -> Result<Vec<EncodedByteBlock>, EncodeError> {
+        let blocks = self.encoder.generate_encoded_blocks(n)?;
+        Ok(blocks.into_iter().map(|(seed, degree, _, data)| EncodedByteBlock { seed, degree, data }).collect())
+    }
+    
+    fn from_data(data: &[u8], block_size: usize, seed: Option<i64>) -> Result<Self, LtError> {
+        if block_size == 0 {
+            return Err(ConfigError::ZeroBlockSize.into());
+        }
+        let source_blocks: Vec<Vec<u8>> = data.chunks(block_size).map(<[u8]>::to_vec).collect();
+        check_source(&source_blocks)?;
+        Ok(Self {
+            encoder: Encoder::new_padded_generic(source_blocks, seed),
+        })
+    }
+}
+
+impl LubyTransformByteDecoder {
+    fn from_len(data_len: usize, block_size: usize) -> Result<Self, ConfigError> {
+        if block_size == 0 {
+            return Err(ConfigError::ZeroBlockSize);
+        }
+        let k = data_len.div_ceil(block_size);
+        let decoder = match k {
+            0 => Decoder::new_default_generic(0, block_size),
+            _ => Decoder::new_with_last_block_size_generic(k, block_size, data_len - (k - 1) * block_size),
+        };
+        Ok(Self { decoder })
+    }
+    
+    fn decoded_bytes(&self) -> Option<Vec<u8>> {
+        Some(self.decoder.get_all_decoded_blocks()?.concat())
+    }
+}
+
+impl LubyTransformDecoder {
+    fn decoded_bytes(&self) -> Option<Vec<u8>> {
+        let blocks = self.decoder.get_all_decoded_blocks()?;
+        Some(blocks.iter().flatten().map(|&symbol| symbol as u8).collect())
+    }
+    
+    fn packed_bytes(&self, block_byte_len: usize, byte_len: usize, order: ByteOrder) -> Option<Vec<u8>> {
+        let blocks = unpack_blocks(&self.decoder.get_all_decoded_blocks()?, order);
+        let mut bytes: Vec<u8> = blocks.iter().flat_map(|block| &block[..block_byte_len.min(block.len())]).copied().collect();
+        bytes.truncate(byte_len);
+        Some(bytes)
+    }
+}
+
+/// Converts a JS array of typed arrays of type `expected`, failing on the
+/// first entry of another type
+fn typed_blocks<A: JsCast, T>(source_blocks: js_sys::Array, expected: &'static str, to_vec: impl Fn(A) -> Vec<T>) -> Result<Vec<Vec<T>>, EncodeError> {
+    source_blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| block.dyn_into::<A>().map(&to_vec).map_err(|_| EncodeError::WrongBlockType { index, expected }))
+        .collect()
+}
+
+/// Converts a JS array of `Int32Array` blocks, failing on other values
+fn int32_blocks(source_blocks: js_sys::Array) -> Result<Vec<Vec<i32>>, EncodeError> {
+    typed_blocks(source_blocks, "Int32Array", |block: js_sys::Int32Array| block.to_vec())
+}
+
+/// Rejects source data the encoder cannot sample from: no blocks at all, or
+/// blocks without symbols
+fn check_source<T>(source_blocks: &[Vec<T>]) -> Result<(), EncodeError> {
+    if source_blocks.is_empty() {
+        return Err(EncodeError::NoSourceBlocks);
+    }
+    match source_blocks.iter().position(Vec::is_empty) {
+        Some(index) => Err(EncodeError::EmptyBlock { index }),
+        None => Ok(()),
+    }
+}
+
+/// Encodes `num_blocks` blocks from `source` with default parameters
+///
+/// This is the native counterpart of `encode_file_blocks`. Each block
+/// carries the source indices it combines. Fails on empty input, see
+/// `LubyTransformEncoder::new`.
+pub fn encode_blocks(source: Vec<Vec<i32>>, seed: Option<i64>, num_blocks: usize) -> Result<Vec<EncodedBlock>, EncodeError> {
+    LubyTransformEncoder::from_source(source, seed)?.next_blocks(num_blocks)
+}
+
+#[wasm_bindgen]
+pub fn encode_file_blocks(blocks: js_sys::Array, seed: Option<i64>, num_encoded_blocks: usize) -> Result<Array, JsValue> {
+    let blocks = encode_blocks(int32_blocks(blocks)?, seed, num_encoded_blocks)?;
+    Ok(blocks.into_iter().map(JsValue::from).collect())
+}
+
+/// Packs bytes into `i32` symbols, four per symbol (little-endian by default)
+///
+/// Encoder and decoder sides must use the same byte order.
+#[wasm_bindgen]
+pub fn bytes_to_symbols(bytes: &[u8], order: Option<ByteOrder>) -> Vec<i32> {
+    luby_transform::bytes::bytes_to_symbols(bytes, order.unwrap_or_default())
+}
+
+/// Unpacks `i32` symbols into bytes (little-endian by default)
+#[wasm_bindgen]
+pub fn symbols_to_bytes(symbols: &[i32], order: Option<ByteOrder>) -> Vec<u8> {
+    luby_transform::bytes::symbols_to_bytes(symbols, order.unwrap_or_default())
+}
+
+/// Recommends how many encoded blocks to send so that `k` source blocks
+/// decode with probability at least `target_prob`
+///
+/// Throws if `delta`, `c` or `target_prob` is out of range.
+#[wasm_bindgen]
+pub fn estimate_blocks_needed(k: usize, delta: f64, c: f64, target_prob: f64) -> Result<usize, JsValue> {
+    Ok(luby_transform::estimate::estimate_blocks_needed(k, delta, c, target_prob).map_err(LtError::from)?)
+}
+
+#[wasm_bindgen]
+pub fn init() {
+    // This function can be called to ensure the wasm module is initialized properly
+    // It can also be used to perform any setup tasks if needed
+}
+
+// Remove default export of function pointer as wasm-bindgen doesn't support it
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_encoded_block_bytes_round_trip() {
+        let block = EncodedBlock::new(-7_000_000_000, 3, &[1, -2, i32::MAX, i32::MIN]);
+        let bytes = block.to_bytes();
+        assert_eq!(bytes.len(), BLOCK_HEADER_LEN + 16);
+        assert_eq!(EncodedBlock::from_bytes(&bytes), Ok(block));
+        
+        let empty = EncodedBlock::new(1, 1, &[]);
+        assert_eq!(EncodedBlock::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+    
+    #[test]
+    fn test_encoded_block_rejects_malformed() {
+        let bytes = EncodedBlock::new(42, 2, &[5, 6]).to_bytes();
+        
+        assert_eq!(
+            EncodedBlock::from_bytes(&bytes[..10]),
+            Err(ParseError::TruncatedHeader { len: 10 })
+        );
+        assert_eq!(
+            EncodedBlock::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ParseError::LengthMismatch { declared: 8, available: 7 })
+        );
+        
+        // Length field claims more symbols than the buffer holds
+        let mut bad_len = bytes.clone();
+        bad_len[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            EncodedBlock::from_bytes(&bad_len),
+            Err(ParseError::LengthMismatch { available: 8, .. })
+        ));
+    }
+    
+    #[test]
+    fn test_corrupted_block_rejected() {
+        let source_blocks: Vec<Vec<i32>> = (0..8).map(|i| vec![i, i * 5, -i]).collect();
+        let mut encoder = LubyTransformEncoder {
+            encoder: Encoder::new_default(source_blocks, Some(3)),
+            checksums: true,
+            byte_lens: None,
+        };
+        let block = encoder.generate_block(None).unwrap();
+        assert!(block.crc().is_some() && block.verify_crc());
+        
+        // The CRC survives the wire format
+        let mut bytes = block.to_bytes();
+        assert_eq!(bytes.len(), BLOCK_HEADER_LEN + 12 + 4);
+        let parsed = EncodedBlock::from_bytes(&bytes).unwrap();
+        assert_eq!((parsed.seed, parsed.crc()), (block.seed, block.crc()));
+        assert!(parsed.verify_crc());
+        
+        // Flip one payload bit
+        bytes[BLOCK_HEADER_LEN + 5] ^= 0x10;
+        let corrupted = EncodedBlock::from_bytes(&bytes).unwrap();
+        assert!(!corrupted.verify_crc());
+        
+        let mut decoder = LubyTransformDecoder::new(8, 3);
+        assert_eq!(decoder.add_block(&corrupted), None);
+        assert_eq!(decoder.blocks_corrupted(), 1);
+        assert_eq!(decoder.blocks_received(), 0);
+        assert!(decoder.add_block(&block).is_some());
+    }
+    
+    #[test]
+    fn test_decoded_bytes() {
+        let bytes: Vec<u8> = (0..=255).chain(0..50).collect();
+        let source_blocks: Vec<Vec<i32>> = bytes.chunks(16).map(|chunk| {
+            let mut block: Vec<i32> = chunk.iter().map(|&b| b as i32).collect();
+            block.resize(16, 0);
+            block
+        }).collect();
+        let k = source_blocks.len();
+        
+        let mut encoder = Encoder::new_default(source_blocks, Some(8));
+        let mut decoder = LubyTransformDecoder::with_last_block_size(k, 16, bytes.len() % 16);
+        assert_eq!(decoder.decoded_bytes(), None);
+        while !decoder.is_complete() {
+            let (seed, d, _, data) = encoder.generate_encoded_block(None).unwrap();
+            decoder.add_encoded_block(seed, d, &data);
+        }
+        assert_eq!(decoder.decoded_bytes(), Some(bytes));
+    }
+    
+    #[test]
+    fn test_byte_round_trip() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let mut encoder = LubyTransformByteEncoder::from_data(&data, 64, Some(5)).unwrap();
+        assert_eq!(encoder.source_block_count(), 16);
+        
+        let mut decoder = LubyTransformByteDecoder::from_len(data.len(), 64).unwrap();
+        assert_eq!(decoder.decoded_bytes(), None);
+        while !decoder.is_complete() {
+            let block = encoder.generate_block(None).unwrap();
+            assert_eq!(block.data.len(), 64);
+            decoder.add_block(&block);
+        }
+        assert_eq!(decoder.decoded_bytes(), Some(data.clone()));
+        
+        // Batches continue the same stream
+        let mut batched = LubyTransformByteEncoder::from_data(&data, 64, Some(5)).unwrap();
+        let mut single = LubyTransformByteEncoder::from_data(&data, 64, Some(5)).unwrap();
+        for block in batched.next_blocks(5).unwrap() {
+            let expected = single.generate_block(None).unwrap();
+            assert_eq!((block.seed, block.degree, block.data), (expected.seed, expected.degree, expected.data));
+        }
+        
+        assert!(LubyTransformByteEncoder::from_data(&[], 64, None).is_err());
+        assert_eq!(
+            LubyTransformByteEncoder::from_data(&[1, 2], 0, None).err(),
+            Some(LtError::Config(ConfigError::ZeroBlockSize))
+        );
+        assert!(LubyTransformByteDecoder::from_len(0, 64).unwrap().is_complete());
+        assert_eq!(LubyTransformByteDecoder::from_len(10, 0).err(), Some(ConfigError::ZeroBlockSize));
+    }
+    
+    #[test]
+    fn test_packed_bytes_uneven_last_block() {
+        let data: Vec<u8> = (1..=23).collect();
+        let byte_blocks: Vec<Vec<u8>> = data.chunks(10).map(<[u8]>::to_vec).collect();
+        let mut encoder = LubyTransformEncoder::from_byte_blocks(byte_blocks, Some(9), ByteOrder::Big).unwrap();
+        assert_eq!((encoder.block_size(), encoder.block_byte_len(), encoder.byte_len()), (3, Some(10), Some(23)));
+        
+        let mut decoder = LubyTransformDecoder::new(3, 3);
+        assert_eq!(decoder.packed_bytes(10, 23, ByteOrder::Big), None);
+        while !decoder.is_complete() {
+            decoder.add_block(&encoder.generate_block(None).unwrap());
+        }
+        assert_eq!(decoder.packed_bytes(10, 23, ByteOrder::Big), Some(data));
+        
+        // Only the last block may be shorter
+        let uneven = vec![vec![1; 8], vec![2; 6], vec![3; 8]];
+        assert_eq!(
+            LubyTransformEncoder::from_byte_blocks(uneven, None, ByteOrder::Little).err(),
+            Some(EncodeError::MismatchedBlockLength { index: 1, expected: 8, found: 6 })
+        );
+        let longer = vec![vec![1; 8], vec![2; 9]];
+        assert_eq!(
+            LubyTransformEncoder::from_byte_blocks(longer, None, ByteOrder::Little).err(),
+            Some(EncodeError::MismatchedBlockLength { index: 1, expected: 8, found: 9 })
+        );
+    }
+    
+    #[test]
+    fn test_encode_blocks() {
+        let k = 12;
+        let source: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * 3]).collect();
+        let blocks = encode_blocks(source.clone(), Some(4), 10).unwrap();
+        assert_eq!(blocks.len(), 10);
+        for block in &blocks {
+            assert!((1..=k).contains(&block.degree));
+            assert_eq!(block.indices.len(), block.degree);
+            assert!(block.indices.iter().all(|&idx| idx < k));
+        }
+        
+        assert_eq!(encode_blocks(source, Some(4), 10), Ok(blocks));
+        assert_eq!(encode_blocks(Vec::new(), None, 1), Err(EncodeError::NoSourceBlocks));
+    }
+    
+    #[test]
+    fn test_empty_source_rejected() {
+        // The JS constructor goes through `from_source`
+        assert!(matches!(
+            LubyTransformEncoder::from_source(Vec::new(), None),
+            Err(EncodeError::NoSourceBlocks)
+        ));
+        assert!(matches!(
+            LubyTransformEncoder::from_source(vec![vec![1], vec![], vec![3]], None),
+            Err(EncodeError::EmptyBlock { index: 1 })
+        ));
+        assert_eq!(encode_blocks(vec![vec![]], None, 1), Err(EncodeError::EmptyBlock { index: 0 }));
+        
+        let mut encoder = LubyTransformEncoder::from_source(vec![vec![1], vec![2]], Some(1)).unwrap();
+        assert!(encoder.generate_block(None).is_ok());
+    }
+    
+    #[test]
+    fn test_decode_without_indices() {
+        let k = 10;
+        let source: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i * 11, -i]).collect();
+        let mut decoder = LubyTransformDecoder { decoder: Decoder::new_default(k, 2) };
+        
+        // Rebuild every block through the JS constructor, dropping its indices
+        for block in encode_blocks(source.clone(), Some(6), 20 * k).unwrap() {
+            let mut rebuilt = EncodedBlock::new(block.seed(), block.degree(), block.symbols());
+            assert!(rebuilt.indices().is_empty());
+            decoder.add_block(&rebuilt);
+            
+            rebuilt.set_indices(block.indices().into_iter().rev().collect());
+            assert_eq!(rebuilt, block);
+            if decoder.is_complete() {
+                break;
+            }
+        }
+        assert_eq!(decoder.decoder.get_all_decoded_blocks(), Some(source));
+    }
+}
+
+// Tests of the JS-facing signatures, which need a JS engine:
+// `wasm-pack test --node`
+#[cfg(all(test, target_arch = "wasm32"))]
+mod js_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+    
+    fn message(err: JsValue) -> String {
+        err.dyn_into::<js_sys::Error>().unwrap().message().into()
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_progress_callback_throws() {
+        let k = 8;
+        let source: Vec<Vec<i32>> = (0..k as i32).map(|i| vec![i, i * 9]).collect();
+        let mut encoder = LubyTransformEncoder::from_source(source.clone(), Some(2)).unwrap();
+        let mut decoder = LubyTransformDecoder::new(k, 2);
+        
+        // The callback records its arguments, then throws
+        let calls = Array::new();
+        let cb = js_sys::Function::new_with_args("decoded, k", "this.push([decoded, k]); throw new Error('progress');").bind(&calls);
+        while !decoder.is_complete() {
+            let block = encoder.generate_block(None).unwrap();
+            decoder.add_encoded_block_with_progress(block.seed(), block.degree(), block.symbols(), &cb);
+        }
+        assert_eq!(decoder.decoder.get_all_decoded_blocks(), Some(source));
+        
+        // The last call reports completion
+        assert!(calls.length() > 0);
+        let last = Array::from(&calls.get(calls.length() - 1));
+        assert_eq!((last.get(0).as_f64(), last.get(1).as_f64()), (Some(k as f64), Some(k as f64)));
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_bad_source_throws() {
+        let err = LubyTransformEncoder::new(Array::new(), None).err().unwrap();
+        assert_eq!(message(err), EncodeError::NoSourceBlocks.to_string());
+        let err = encode_file_blocks(Array::new(), None, 3).err().unwrap();
+        assert_eq!(message(err), EncodeError::NoSourceBlocks.to_string());
+        
+        let empty = Array::of2(&js_sys::Int32Array::from(&[1][..]), &js_sys::Int32Array::new_with_length(0));
+        let err = LubyTransformEncoder::new(empty, None).err().unwrap();
+        assert_eq!(message(err), EncodeError::EmptyBlock { index: 1 }.to_string());
+        
+        // Entries of another type are named rather than skipped
+        let mixed = Array::of2(&js_sys::Int32Array::from(&[1, 2][..]), &JsValue::from_str("3, 4"));
+        let err = LubyTransformEncoder::new(mixed, None).err().unwrap();
+        assert_eq!(message(err), EncodeError::WrongBlockType { index: 1, expected: "Int32Array" }.to_string());
+        let ints = Array::of1(&js_sys::Int32Array::from(&[1, 2][..]));
+        let err = LubyTransformEncoder::from_bytes(ints, None, None).err().unwrap();
+        assert_eq!(message(err), EncodeError::WrongBlockType { index: 0, expected: "Uint8Array" }.to_string());
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_byte_orders_round_trip() {
+        let data: Vec<u8> = (0..40).map(|i| i * 5 + 1).collect();
+        let blocks = || data.chunks(12).map(|chunk| JsValue::from(js_sys::Uint8Array::from(chunk))).collect::<Array>();
+        
+        for order in [ByteOrder::Little, ByteOrder::Big] {
+            let mut encoder = LubyTransformEncoder::from_bytes(blocks(), Some(3), Some(order)).unwrap();
+            let (block_byte_len, byte_len) = (encoder.block_byte_len().unwrap(), encoder.byte_len().unwrap());
+            let mut decoder = LubyTransformDecoder::new(encoder.source_block_count(), encoder.block_size());
+            while !decoder.is_complete() {
+                decoder.add_block(&encoder.generate_block(None).unwrap());
+            }
+            let bytes = decoder.get_decoded_packed_bytes(block_byte_len, byte_len, Some(order)).unwrap();
+            assert_eq!(bytes.to_vec(), data);
+            
+            // The other order swaps the bytes of every symbol
+            let other = if order == ByteOrder::Little { ByteOrder::Big } else { ByteOrder::Little };
+            let swapped = decoder.get_decoded_packed_bytes(block_byte_len, byte_len, Some(other)).unwrap();
+            assert_ne!(swapped.to_vec(), data);
+        }
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_decoded_bytes() {
+        let data = b"luby transform codes";
+        let blocks: Array = data
+            .chunks(6)
+            .map(|chunk| {
+                // One byte per symbol, the final block zero-padded to six
+                let mut symbols: Vec<i32> = chunk.iter().map(|&byte| byte as i32).collect();
+                symbols.resize(6, 0);
+                JsValue::from(js_sys::Int32Array::from(&symbols[..]))
+            })
+            .collect();
+        let mut encoder = LubyTransformEncoder::new(blocks, Some(8)).unwrap();
+        
+        let mut decoder = LubyTransformDecoder::with_last_block_size(4, 6, 2);
+        assert!(decoder.get_decoded_bytes().is_none());
+        while !decoder.is_complete() {
+            decoder.add_block(&encoder.generate_block(None).unwrap());
+        }
+        assert_eq!(decoder.get_decoded_bytes().unwrap().to_vec(), data);
+    }
+}